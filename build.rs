@@ -0,0 +1,23 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Passes the git revision the program is built from to the manifests of the runs, if the program is built from a git repository.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let head = Path::new(".git").join("HEAD");
+    if let Ok(contents) = std::fs::read_to_string(&head) {
+        println!("cargo:rerun-if-changed={}", head.display());
+        // A new commit changes the branch the HEAD points to, not the HEAD itself
+        if let Some(reference) = contents.trim().strip_prefix("ref: ") {
+            let reference = Path::new(".git").join(reference);
+            if reference.exists() {
+                println!("cargo:rerun-if-changed={}", reference.display());
+            }
+        }
+    }
+
+    let revision = Command::new("git").args(["rev-parse", "HEAD"]).output().ok().filter(|output| output.status.success());
+    if let Some(revision) = revision.and_then(|output| String::from_utf8(output.stdout).ok()) {
+        println!("cargo:rustc-env=MARCO_GIT_REVISION={}", revision.trim());
+    }
+}
@@ -2,21 +2,110 @@ use std::collections::HashMap;
 
 use eframe::egui;
 
-use crate::{data, message_passers, settings, simulator};
+use crate::{checkpoint, critical_velocity, data, eclipse, export, history, manifest, message_passers, progress, runs, settings, simulator, summary, time_unit};
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum WindowToShow {
     LatitudeVsTimeGraph,
     LongitudeVsTimeGraph,
+    SubsolarDistanceVsTimeGraph,
+    SubsolarBearingVsTimeGraph,
+    SpeedVsTimeGraph,
+    BatteryChargeVsTimeGraph,
+    RunDifferenceGraph,
+    BifurcationGraph,
+    CriticalVelocityGraph,
+    FlowField,
+    EclipseList,
     Nothing,
 }
 
+impl WindowToShow {
+    pub const ALL: [Self; 12] = [
+        Self::LatitudeVsTimeGraph,
+        Self::LongitudeVsTimeGraph,
+        Self::SubsolarDistanceVsTimeGraph,
+        Self::SubsolarBearingVsTimeGraph,
+        Self::SpeedVsTimeGraph,
+        Self::BatteryChargeVsTimeGraph,
+        Self::RunDifferenceGraph,
+        Self::BifurcationGraph,
+        Self::CriticalVelocityGraph,
+        Self::FlowField,
+        Self::EclipseList,
+        Self::Nothing,
+    ];
+
+    pub fn description(&self) -> Option<&'static str> {
+        match self {
+            Self::SubsolarDistanceVsTimeGraph => Some("The angular distance between Marco and the point on the planet where the star is in the zenith"),
+            Self::SubsolarBearingVsTimeGraph => Some("The direction Marco has to face to look at the star, measured clockwise from north"),
+            Self::SpeedVsTimeGraph => Some("The speed Marco actually moves at, which is zero at night and limited by the energy available with the rover energy model"),
+            Self::BatteryChargeVsTimeGraph => Some("The charge left in the battery of the rover, which is always full without the rover energy model"),
+            Self::BifurcationGraph => Some("Summarises the end of the run of every velocity, showing how the behaviour changes with the velocity"),
+            Self::CriticalVelocityGraph => Some("The slowest velocity Marco needs to stay in permanent daylight or to reach a pole, depending on his starting latitude"),
+            Self::FlowField => Some("Where Marcos from every starting position end up, useful with a grid of starting positions"),
+            Self::EclipseList => Some("The eclipses of the star by the moon every Marco has seen, with a moon enabled in the settings"),
+            _ => None,
+        }
+    }
+}
+
+impl AsRef<str> for WindowToShow {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::LatitudeVsTimeGraph => "Graph of latitude vs time",
+            Self::LongitudeVsTimeGraph => "Graph of longitude vs time",
+            Self::SubsolarDistanceVsTimeGraph => "Graph of distance from the subsolar point vs time",
+            Self::SubsolarBearingVsTimeGraph => "Graph of bearing to the subsolar point vs time",
+            Self::SpeedVsTimeGraph => "Graph of speed vs time",
+            Self::BatteryChargeVsTimeGraph => "Graph of battery charge vs time",
+            Self::RunDifferenceGraph => "Graph of run differences",
+            Self::BifurcationGraph => "Graph of final state vs velocity",
+            Self::CriticalVelocityGraph => "Graph of critical velocity vs starting latitude",
+            Self::FlowField => "Flow field of the starting positions",
+            Self::EclipseList => "List of eclipses",
+            Self::Nothing => "Nothing",
+        }
+    }
+}
+
 pub struct Application {
     window_to_show: WindowToShow,
     pub calculation_stage: message_passers::CalculationStage,
-    pub data: HashMap<egui::Color32, Vec<data::Data>>,
+    pub progress: Option<progress::Progress>,
+    pub data: HashMap<usize, Vec<data::Data>>,
+    /// The eclipses of the current run, in the order they ended.
+    pub eclipses: Vec<eclipse::Eclipse>,
+    pub current_run_settings: settings::Settings,
+    pub show_current_run: bool,
+    pub show_subsolar_point: bool,
+    pub time_unit: time_unit::TimeUnit,
+    pub bifurcation_summary: summary::Summary,
+    pub bifurcation_window_days: f64,
+    pub critical_velocity_search: critical_velocity::Search,
+    pub critical_velocities: Vec<(f64, Option<f64>)>,
+    /// The parameters and the settings of the last search for critical velocities, recorded in the manifest when they are exported.
+    pub critical_velocity_run: Option<(critical_velocity::Search, history::RunLogEntry)>,
+    pub pinned_runs: Vec<runs::PinnedRun>,
+    pub run_comparison: runs::RunComparison,
     pub windows_opened: WindowsOpened,
     pub settings: settings::Settings,
+    /// The text the list of starting positions is edited in, it is parsed into the settings.
+    pub start_list_text: String,
+    pub start_picker_adds: bool,
+    /// The text the start date is edited in, it is parsed into the settings.
+    pub start_date_text: String,
+    /// The path the terrain mask is loaded from.
+    pub terrain_mask_path: String,
+    pub settings_history: history::SettingsHistory,
+    pub run_log: Vec<history::RunLogEntry>,
+    pub notifications: Vec<Notification>,
+    /// The checkpoint that can be resumed, if there is one, and when the checkpoint file was last looked for.
+    pub checkpoint_summary: Option<checkpoint::Summary>,
+    pub checkpoint_checked: Option<std::time::Instant>,
+    /// Whether the user is asked to confirm that recalculating replaces the checkpoint that can be resumed.
+    pub confirm_checkpoint_replacement: bool,
 
     pub message_passers: message_passers::MessagePassers,
 }
@@ -28,9 +117,32 @@ impl Application {
         Self {
             window_to_show: WindowToShow::LatitudeVsTimeGraph,
             calculation_stage: message_passers::CalculationStage::End,
+            progress: None,
             data: HashMap::new(),
+            eclipses: Vec::new(),
+            current_run_settings: settings::Settings::default(),
+            show_current_run: true,
+            show_subsolar_point: true,
+            time_unit: time_unit::TimeUnit::default(),
+            bifurcation_summary: summary::Summary::default(),
+            bifurcation_window_days: 1.0,
+            critical_velocity_search: critical_velocity::Search::default(),
+            critical_velocities: Vec::new(),
+            critical_velocity_run: None,
+            pinned_runs: Vec::new(),
+            run_comparison: runs::RunComparison::default(),
             windows_opened: WindowsOpened::default(),
             settings: settings::Settings::default(),
+            start_list_text: String::new(),
+            start_picker_adds: false,
+            start_date_text: String::new(),
+            terrain_mask_path: String::new(),
+            settings_history: history::SettingsHistory::default(),
+            run_log: Vec::new(),
+            notifications: Vec::new(),
+            checkpoint_summary: None,
+            checkpoint_checked: None,
+            confirm_checkpoint_replacement: false,
 
             message_passers: message_passers::MessagePassers::default(),
         }
@@ -39,21 +151,52 @@ impl Application {
 
 impl eframe::App for Application {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
-        while let Ok(data) = self.message_passers.calculator_to_main_receiver.try_recv() {
+        // The calculator can produce messages faster than they are handled, so only a limited number is handled each frame to keep the GUI responsive
+        for data in self.message_passers.calculator_to_main_receiver.try_iter().take(message_passers::CALCULATOR_TO_MAIN_CAPACITY) {
             match data {
-                message_passers::Message::NewPoint(point) => {
-                    let entry = self.data.entry(point.colour).or_default();
-                    entry.push(point);
+                message_passers::Message::NewPoints(batch) => {
+                    for points in batch {
+                        if let Some(first) = points.first() {
+                            let entry = self.data.entry(first.series).or_default();
+                            entry.extend(points);
+                        }
+                    }
+                }
+                message_passers::Message::NewStage(stage) => {
+                    if stage == message_passers::CalculationStage::End {
+                        if let Some(entry) = self.run_log.last_mut().filter(|entry| entry.duration.is_none()) {
+                            entry.finish(&self.data);
+                        }
+                        if let Some((_, entry)) = self.critical_velocity_run.as_mut().filter(|(_, entry)| entry.duration.is_none()) {
+                            entry.duration = Some(entry.started.elapsed());
+                        }
+                    }
+                    self.calculation_stage = stage;
+                    self.progress = None;
                 }
-                message_passers::Message::NewStage(stage) => self.calculation_stage = stage,
+                message_passers::Message::Progress(progress) => self.progress = Some(progress),
+                message_passers::Message::CriticalVelocity(latitude, velocity) => self.critical_velocities.push((latitude, velocity)),
+                message_passers::Message::Eclipse(eclipse) => self.eclipses.push(eclipse),
+                message_passers::Message::ImageSaved(path) => self.notifications.push(Notification::info(format!("The path image has been saved to {}", path.display()))),
+                message_passers::Message::Error(err) => self.notifications.push(Notification::error(err.to_string())),
             }
         }
+        // The checkpoint file is written and removed by the calculator, so it is looked for again every now and then
+        if self.checkpoint_checked.is_none_or(|checked| checked.elapsed() >= CHECKPOINT_CHECK_INTERVAL) {
+            self.checkpoint_summary = checkpoint::read_summary(&simulator::checkpoint_path()).ok();
+            self.checkpoint_checked = Some(std::time::Instant::now());
+        }
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
-                    ui.selectable_value(&mut self.window_to_show, WindowToShow::LatitudeVsTimeGraph, "Graph of latitude vs time");
-                    ui.selectable_value(&mut self.window_to_show, WindowToShow::LongitudeVsTimeGraph, "Graph of longitude vs time");
-                    ui.selectable_value(&mut self.window_to_show, WindowToShow::Nothing, "Nothing");
+                    egui::ComboBox::from_id_source("window_to_show").selected_text(self.window_to_show.as_ref()).show_ui(ui, |ui| {
+                        for window in WindowToShow::ALL {
+                            let response = ui.selectable_value(&mut self.window_to_show, window, window.as_ref());
+                            if let Some(description) = window.description() {
+                                response.on_hover_text(description);
+                            }
+                        }
+                    });
                 });
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     match self.calculation_stage {
@@ -61,31 +204,80 @@ impl eframe::App for Application {
                             if ui.button("Recalculate").clicked() {
                                 self.recalculate();
                             }
+                            let hover_text = match self.checkpoint_summary {
+                                Some(summary) => format!(
+                                    "Continue the run saved in the checkpoint file, which has simulated {} out of {} and was written {} ago",
+                                    time_unit::format_calendar(summary.time),
+                                    time_unit::format_calendar(summary.simulation_time),
+                                    progress::format_duration(summary.written.elapsed().unwrap_or_default())
+                                ),
+                                None => String::from("There is no checkpoint to continue, enable \"Write checkpoints\" in the settings to write one while running"),
+                            };
+                            if ui
+                                .add_enabled(self.checkpoint_summary.is_some(), egui::Button::new("Resume"))
+                                .on_hover_text(&hover_text)
+                                .on_disabled_hover_text(&hover_text)
+                                .clicked()
+                            {
+                                self.resume_from_checkpoint();
+                            }
                         }
-                        message_passers::CalculationStage::Plots | message_passers::CalculationStage::Start => {
+                        message_passers::CalculationStage::Start => {
                             ui.add_enabled(false, egui::Button::new(self.calculation_stage.as_ref()));
                         }
-                        message_passers::CalculationStage::Points => {
-                            ui.add_enabled(
-                                false,
-                                egui::Button::new(format!(
-                                    "{} (~{:.5}%)",
-                                    self.calculation_stage.as_ref(),
-                                    self.data.values().map(|v| v.len()).sum::<usize>() as f32 / ((self.settings.points_to_show * self.settings.velocities_count) as f32) * 100.0
-                                )),
-                            );
+                        message_passers::CalculationStage::Points | message_passers::CalculationStage::Plots | message_passers::CalculationStage::CriticalVelocities => {
+                            let text = match self.progress {
+                                Some(progress) => {
+                                    let unit = match self.calculation_stage {
+                                        message_passers::CalculationStage::Points => "steps/s",
+                                        message_passers::CalculationStage::Plots => "lines/s",
+                                        _ => "latitudes/s",
+                                    };
+                                    let eta = progress.eta.map(|eta| format!(", ~{} left", progress::format_duration(eta))).unwrap_or_default();
+                                    format!(
+                                        "{} ({:.3}%, {:.0} {unit}{eta})",
+                                        self.calculation_stage.as_ref(),
+                                        progress.fraction() * 100.0,
+                                        progress.steps_per_second
+                                    )
+                                }
+                                None => String::from(self.calculation_stage.as_ref()),
+                            };
+                            let response = ui.add_enabled(false, egui::Button::new(text));
+                            if let (Some(progress), message_passers::CalculationStage::Points) = (self.progress, &self.calculation_stage) {
+                                response.on_disabled_hover_text(format!("Simulated {:.3} s out of {:.3} s", progress.done, progress.total));
+                            }
                         }
                     }
                     if ui.button("Settings").clicked() {
                         self.windows_opened.settings = true;
                     }
+                    if ui.button("Runs").clicked() {
+                        self.windows_opened.runs = true;
+                    }
+                    if ui.button("History").clicked() {
+                        self.windows_opened.history = true;
+                    }
                 });
             });
         });
+        self.render_notifications(ctx);
+        self.render_checkpoint_replacement(ctx);
         self.render_settings(ctx);
+        self.render_runs(ctx);
+        self.render_history(ctx);
         egui::CentralPanel::default().show(ctx, |ui| match self.window_to_show {
             WindowToShow::LatitudeVsTimeGraph => self.render_latitude_vs_time_graph(ui),
             WindowToShow::LongitudeVsTimeGraph => self.render_longitude_vs_time_graph(ui),
+            WindowToShow::SubsolarDistanceVsTimeGraph => self.render_subsolar_distance_vs_time_graph(ui),
+            WindowToShow::SubsolarBearingVsTimeGraph => self.render_subsolar_bearing_vs_time_graph(ui),
+            WindowToShow::RunDifferenceGraph => self.render_run_difference_graph(ui),
+            WindowToShow::SpeedVsTimeGraph => self.render_speed_vs_time_graph(ui),
+            WindowToShow::BatteryChargeVsTimeGraph => self.render_battery_charge_vs_time_graph(ui),
+            WindowToShow::BifurcationGraph => self.render_bifurcation_graph(ui),
+            WindowToShow::CriticalVelocityGraph => self.render_critical_velocity_graph(ui),
+            WindowToShow::FlowField => self.render_flow_field(ui),
+            WindowToShow::EclipseList => self.render_eclipse_list(ui),
             WindowToShow::Nothing => {}
         });
         ctx.request_repaint();
@@ -93,17 +285,138 @@ impl eframe::App for Application {
 }
 
 impl Application {
+    /// Recalculates the simulation, unless the new run would replace the checkpoint that can be resumed, which the user is asked to confirm first.
     pub fn recalculate(&mut self) {
+        if self.settings.checkpoint && self.checkpoint_summary.is_some() {
+            self.confirm_checkpoint_replacement = true;
+        } else {
+            self.recalculate_replacing_checkpoint();
+        }
+    }
+
+    pub fn recalculate_replacing_checkpoint(&mut self) {
+        self.confirm_checkpoint_replacement = false;
         self.data = HashMap::new();
+        self.eclipses = Vec::new();
         self.calculation_stage = message_passers::CalculationStage::Start;
+        self.progress = None;
 
-        let settings = self.settings;
+        let settings = self.settings.clone();
+        self.current_run_settings = settings.clone();
+        self.run_log.push(history::RunLogEntry::new(settings.clone()));
         let sender = self.message_passers.calculator_to_main_sender.clone();
         std::thread::spawn(move || simulator::recalculate_simulation(settings, sender));
     }
+
+    pub fn resume_from_checkpoint(&mut self) {
+        let path = simulator::checkpoint_path();
+        let checkpoint = match checkpoint::read(&path) {
+            Ok(checkpoint) => checkpoint,
+            Err(err) => {
+                self.notifications.push(Notification::error(format!("Failed to read the checkpoint {}: {err}", path.display())));
+                return;
+            }
+        };
+        self.data = HashMap::new();
+        self.eclipses = Vec::new();
+        self.calculation_stage = message_passers::CalculationStage::Start;
+        self.progress = None;
+
+        let settings = checkpoint.simulation.settings.clone();
+        self.settings_history.replace(&mut self.settings, settings.clone());
+        self.current_run_settings = settings.clone();
+        self.run_log.push(history::RunLogEntry::new(settings));
+        let sender = self.message_passers.calculator_to_main_sender.clone();
+        std::thread::spawn(move || simulator::resume_simulation(checkpoint, sender));
+    }
+
+    pub fn search_critical_velocities(&mut self) {
+        self.critical_velocities = Vec::new();
+        self.calculation_stage = message_passers::CalculationStage::Start;
+        self.progress = None;
+
+        let settings = self.settings.clone();
+        let search = self.critical_velocity_search;
+        self.critical_velocity_run = Some((search, history::RunLogEntry::new(settings.clone())));
+        let sender = self.message_passers.calculator_to_main_sender.clone();
+        std::thread::spawn(move || critical_velocity::recalculate_critical_velocities(settings, search, sender));
+    }
+
+    pub fn pin_current_run(&mut self) {
+        self.pinned_runs.push(runs::PinnedRun {
+            name: format!("Run {}", self.pinned_runs.len() + 1),
+            visible: true,
+            settings: self.current_run_settings.clone(),
+            data: self.data.clone(),
+        });
+    }
+
+    pub fn export_current_run(&mut self) {
+        let path = std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join("points.csv");
+        let duration = self.run_log.last().and_then(|entry| entry.duration);
+        let result = export::write_csv(&path, &runs::ordered_series(&self.data)).and_then(|()| manifest::write(&path, &self.current_run_settings, &[], duration));
+        match result {
+            Ok(manifest_path) => self.notifications.push(Notification::info(format!(
+                "The current run has been exported to {} and described in {}",
+                path.display(),
+                manifest_path.display()
+            ))),
+            Err(err) => self.notifications.push(Notification::error(format!("Failed to export the current run: {err}"))),
+        }
+    }
+
+    pub fn export_eclipses(&mut self) {
+        let path = std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join("eclipses.csv");
+        let duration = self.run_log.last().and_then(|entry| entry.duration);
+        let result = export::write_eclipse_csv(&path, &self.eclipses).and_then(|()| manifest::write(&path, &self.current_run_settings, &[], duration));
+        match result {
+            Ok(manifest_path) => self.notifications.push(Notification::info(format!(
+                "The eclipses have been exported to {} and described in {}",
+                path.display(),
+                manifest_path.display()
+            ))),
+            Err(err) => self.notifications.push(Notification::error(format!("Failed to export the eclipses: {err}"))),
+        }
+    }
+
+    pub fn export_critical_velocities(&mut self) {
+        let path = std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join("critical_velocities.csv");
+        let result = export::write_critical_velocity_csv(&path, &self.critical_velocities).and_then(|()| match &self.critical_velocity_run {
+            Some((search, entry)) => manifest::write(&path, &entry.settings, &search.to_key_values(), entry.duration),
+            None => manifest::write(&path, &self.settings, &self.critical_velocity_search.to_key_values(), None),
+        });
+        match result {
+            Ok(manifest_path) => self.notifications.push(Notification::info(format!(
+                "The critical velocities have been exported to {} and described in {}",
+                path.display(),
+                manifest_path.display()
+            ))),
+            Err(err) => self.notifications.push(Notification::error(format!("Failed to export the critical velocities: {err}"))),
+        }
+    }
+}
+
+/// How often the application looks for a checkpoint to resume.
+const CHECKPOINT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+pub struct Notification {
+    pub message: String,
+    pub is_error: bool,
+}
+
+impl Notification {
+    pub fn info(message: String) -> Self {
+        Self { message, is_error: false }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self { message, is_error: true }
+    }
 }
 
 #[derive(Default)]
 pub struct WindowsOpened {
     pub settings: bool,
+    pub runs: bool,
+    pub history: bool,
 }
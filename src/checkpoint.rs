@@ -0,0 +1,341 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use nalgebra::Vector3;
+
+use crate::{data, eclipse, error, settings, simulator};
+
+/// The name of the checkpoint file in the output directory.
+pub const CHECKPOINT_FILE_NAME: &str = "checkpoint.txt";
+
+/// The first line of a checkpoint file, which changes whenever the format does.
+const HEADER: &str = "# Marco on a desert planet checkpoint, version 1";
+
+/// A simulation saved in the middle of a run, with everything needed to continue it exactly as if it was never interrupted.
+pub struct Checkpoint {
+    pub simulation: simulator::Simulation,
+    /// The points sampled before the checkpoint.
+    pub data: Vec<Vec<data::Data>>,
+    /// The direction towards the star the illumination is drawn for in the path image.
+    pub illuminating_sun_pos_norm: Vector3<f64>,
+}
+
+/// What a checkpoint file holds, read without the points, so it is cheap enough to check often.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    /// The simulated time (s) at the checkpoint and the time to simulate in total.
+    pub time: f64,
+    pub simulation_time: f64,
+    /// When the checkpoint was written.
+    pub written: std::time::SystemTime,
+}
+
+/// Writes a checkpoint of the simulation as text, the numbers are written so that they are read back exactly.
+/// The checkpoint is written into a temporary file first, so an interrupted write does not destroy the previous checkpoint.
+pub fn write(path: &Path, simulation: &simulator::Simulation, data: &[Vec<data::Data>], illuminating_sun_pos_norm: Vector3<f64>) -> Result<(), error::Error> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let temporary_path = path.with_extension("tmp");
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&temporary_path)?);
+    writeln!(writer, "{HEADER}")?;
+
+    writeln!(writer, "[settings]")?;
+    for (key, value) in simulation.settings.to_key_values() {
+        writeln!(writer, "{key} = {value}")?;
+    }
+
+    writeln!(writer, "[state]")?;
+    writeln!(writer, "time = {}", simulation.time)?;
+    writeln!(writer, "sun_pos_norm = {}", format_vector(&simulation.sun_pos_norm))?;
+    writeln!(writer, "ecliptic_axis = {}", format_vector(&simulation.ecliptic_axis))?;
+    writeln!(writer, "illuminating_sun_pos_norm = {}", format_vector(&illuminating_sun_pos_norm))?;
+    writeln!(writer, "moon_pos_norm = {}", format_vector(&simulation.moon_pos_norm))?;
+    writeln!(writer, "moon_orbit_axis = {}", format_vector(&simulation.moon_orbit_axis))?;
+    for eclipse in &simulation.eclipses {
+        writeln!(writer, "eclipse = {}", format_eclipse(eclipse))?;
+    }
+    for eclipse in simulation.ongoing_eclipses.iter().flatten() {
+        writeln!(writer, "ongoing_eclipse = {}", format_eclipse(eclipse))?;
+    }
+    for i in 0..simulation.marco_positions.len() {
+        writeln!(
+            writer,
+            "marco = {} {} {} {} {} {}",
+            format_vector(&simulation.marco_positions[i]),
+            simulation.marco_speeds[i],
+            simulation.battery_charges[i],
+            simulation.parked[i],
+            simulation.walked_today[i],
+            simulation.previous_local_solar_times[i]
+        )?;
+    }
+
+    writeln!(writer, "[points]")?;
+    for point in data.iter().flatten() {
+        writeln!(
+            writer,
+            "point = {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+            point.series,
+            point.time,
+            point.latitude,
+            point.longitude,
+            point.velocity,
+            point.speed,
+            point.battery_charge,
+            point.subsolar_distance,
+            point.subsolar_bearing,
+            point.subsolar_latitude,
+            point.subsolar_longitude,
+            point.colour.r,
+            point.colour.g,
+            point.colour.b,
+            point.colour.a
+        )?;
+    }
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+/// Reads a checkpoint written by `write`.
+pub fn read(path: &Path) -> Result<Checkpoint, error::Error> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut lines = reader.lines();
+    if lines.next().transpose()?.as_deref() != Some(HEADER) {
+        return Err(invalid(format!("{} is not a checkpoint of this version", path.display())));
+    }
+
+    let mut section = String::new();
+    let mut settings = Vec::new();
+    let mut state = Vec::new();
+    let mut points = Vec::new();
+    for line in lines {
+        let line = line?;
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = name.to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once(" = ").or_else(|| line.strip_suffix(" =").map(|key| (key, ""))) else {
+            return Err(invalid(format!("\"{line}\" is not a \"key = value\" line")));
+        };
+        let (key, value) = (key.to_string(), value.to_string());
+        match section.as_str() {
+            "settings" => settings.push((key, value)),
+            "state" => state.push((key, value)),
+            "points" if key == "point" => points.push(value),
+            _ => return Err(invalid(format!("Unexpected line \"{line}\""))),
+        }
+    }
+
+    let settings = settings::Settings::from_key_values(settings.iter().map(|(key, value)| (key.as_str(), value.as_str())))?;
+    let mut simulation = simulator::Simulation::new(settings);
+    let mut illuminating_sun_pos_norm = simulation.sun_pos_norm;
+    let mut marco = 0;
+    for (key, value) in &state {
+        match key.as_str() {
+            "time" => simulation.time = parse(value)?,
+            "sun_pos_norm" => simulation.sun_pos_norm = parse_vector(value)?,
+            "ecliptic_axis" => simulation.ecliptic_axis = parse_vector(value)?,
+            "illuminating_sun_pos_norm" => illuminating_sun_pos_norm = parse_vector(value)?,
+            "moon_pos_norm" => simulation.moon_pos_norm = parse_vector(value)?,
+            "moon_orbit_axis" => simulation.moon_orbit_axis = parse_vector(value)?,
+            "eclipse" => simulation.eclipses.push(parse_eclipse(value, simulation.marco_positions.len())?),
+            "ongoing_eclipse" => {
+                let eclipse = parse_eclipse(value, simulation.marco_positions.len())?;
+                simulation.ongoing_eclipses[eclipse.series] = Some(eclipse);
+            }
+            "marco" => {
+                if marco >= simulation.marco_positions.len() {
+                    return Err(invalid(String::from("There are more Marcos than the settings give")));
+                }
+                let values = value.split_whitespace().collect::<Vec<&str>>();
+                let [x, y, z, speed, battery_charge, parked, walked_today, previous_local_solar_time] = values[..] else {
+                    return Err(invalid(format!("\"{value}\" is not a state of Marco")));
+                };
+                simulation.marco_positions[marco] = Vector3::new(parse(x)?, parse(y)?, parse(z)?);
+                simulation.marco_speeds[marco] = parse(speed)?;
+                simulation.battery_charges[marco] = parse(battery_charge)?;
+                simulation.parked[marco] = parse(parked)?;
+                simulation.walked_today[marco] = parse(walked_today)?;
+                simulation.previous_local_solar_times[marco] = parse(previous_local_solar_time)?;
+                marco += 1;
+            }
+            _ => return Err(invalid(format!("Unknown state {key}"))),
+        }
+    }
+    if marco != simulation.marco_positions.len() {
+        return Err(invalid(String::from("There are fewer Marcos than the settings give")));
+    }
+
+    let mut data = vec![Vec::new(); simulation.marco_positions.len()];
+    for value in &points {
+        let values = value.split_whitespace().collect::<Vec<&str>>();
+        let [series, time, latitude, longitude, velocity, speed, battery_charge, subsolar_distance, subsolar_bearing, subsolar_latitude, subsolar_longitude, r, g, b, a] = values[..] else {
+            return Err(invalid(format!("\"{value}\" is not a point")));
+        };
+        let series: usize = parse(series)?;
+        let Some(points) = data.get_mut(series) else {
+            return Err(invalid(format!("There is no series {series}")));
+        };
+        points.push(data::Data {
+            series,
+            time: parse(time)?,
+            latitude: parse(latitude)?,
+            longitude: parse(longitude)?,
+            velocity: parse(velocity)?,
+            speed: parse(speed)?,
+            battery_charge: parse(battery_charge)?,
+            subsolar_distance: parse(subsolar_distance)?,
+            subsolar_bearing: parse(subsolar_bearing)?,
+            subsolar_latitude: parse(subsolar_latitude)?,
+            subsolar_longitude: parse(subsolar_longitude)?,
+            colour: data::Colour::new(parse(r)?, parse(g)?, parse(b)?, parse(a)?),
+        });
+    }
+    // A checkpoint written before any point was sampled would make the resumed simulation sample the starting points again
+    if data.iter().any(|points| points.is_empty()) {
+        return Err(invalid(String::from("Every series needs at least its starting point")));
+    }
+
+    Ok(Checkpoint {
+        simulation,
+        data,
+        illuminating_sun_pos_norm,
+    })
+}
+
+/// Reads how far the simulation in a checkpoint written by `write` got, stopping before its points.
+pub fn read_summary(path: &Path) -> Result<Summary, error::Error> {
+    let file = std::fs::File::open(path)?;
+    let written = file.metadata()?.modified()?;
+    let mut lines = std::io::BufReader::new(file).lines();
+    if lines.next().transpose()?.as_deref() != Some(HEADER) {
+        return Err(invalid(format!("{} is not a checkpoint of this version", path.display())));
+    }
+    let (mut time, mut simulation_time) = (None, None);
+    for line in lines {
+        let line = line?;
+        if line == "[points]" {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("simulation_time = ") {
+            simulation_time = Some(parse(value)?);
+        } else if let Some(value) = line.strip_prefix("time = ") {
+            time = Some(parse(value)?);
+        }
+    }
+    match (time, simulation_time) {
+        (Some(time), Some(simulation_time)) => Ok(Summary { time, simulation_time, written }),
+        _ => Err(invalid(format!("{} has no simulated time", path.display()))),
+    }
+}
+
+/// Removes the checkpoint, so a run that finished or was replaced by a new one can not be resumed by mistake.
+pub fn remove(path: &Path) -> Result<(), error::Error> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+fn invalid(message: String) -> error::Error {
+    error::Error::InvalidCheckpoint(message)
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, error::Error> {
+    value.parse().map_err(|_| invalid(format!("\"{value}\" is not a valid number")))
+}
+
+fn format_vector(vector: &Vector3<f64>) -> String {
+    format!("{} {} {}", vector.x, vector.y, vector.z)
+}
+
+fn parse_vector(value: &str) -> Result<Vector3<f64>, error::Error> {
+    let values = value.split_whitespace().collect::<Vec<&str>>();
+    let [x, y, z] = values[..] else {
+        return Err(invalid(format!("\"{value}\" is not a vector")));
+    };
+    Ok(Vector3::new(parse(x)?, parse(y)?, parse(z)?))
+}
+
+fn format_eclipse(eclipse: &eclipse::Eclipse) -> String {
+    format!("{} {} {} {}", eclipse.series, eclipse.start, eclipse.end, eclipse.max_coverage)
+}
+
+fn parse_eclipse(value: &str, series_count: usize) -> Result<eclipse::Eclipse, error::Error> {
+    let values = value.split_whitespace().collect::<Vec<&str>>();
+    let [series, start, end, max_coverage] = values[..] else {
+        return Err(invalid(format!("\"{value}\" is not an eclipse")));
+    };
+    let series = parse(series)?;
+    if series >= series_count {
+        return Err(invalid(format!("There is no series {series}")));
+    }
+    Ok(eclipse::Eclipse {
+        series,
+        start: parse(start)?,
+        end: parse(end)?,
+        max_coverage: parse(max_coverage)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumed_simulation_is_identical_to_an_uninterrupted_one() {
+        let settings = settings::Settings {
+            generate_image: false,
+            velocities_count: 3,
+            start_lat: 30.0_f64.to_radians(),
+            rover_energy_model: true,
+            rover_battery_capacity: 0.05,
+            limit_walking_hours: true,
+            max_walking_hours: 10.0,
+            timestep: 60.0,
+            simulation_time: 2.0 * 86400.0,
+            points_to_show: 50,
+            ..Default::default()
+        };
+        let uninterrupted = simulator::simulate(settings.clone(), |_, _| Ok(()), |_, _| Ok(())).unwrap();
+
+        let path = std::env::temp_dir().join(format!("marco_checkpoint_test_{}.txt", std::process::id()));
+        let interrupted = simulator::simulate(
+            settings.clone(),
+            |_, _| Ok(()),
+            |simulation, data| {
+                if simulation.time >= 86400.0 {
+                    write(&path, simulation, data, simulation.sun_pos_norm)?;
+                    // Any error stops the simulation
+                    return Err(error::Error::Send(String::from("Interrupted")));
+                }
+                Ok(())
+            },
+        );
+        assert!(interrupted.is_err());
+
+        let summary = read_summary(&path).unwrap();
+        assert!(summary.time >= 86400.0 && summary.simulation_time == settings.simulation_time);
+        let checkpoint = read(&path).unwrap();
+        remove(&path).unwrap();
+        assert!(!path.exists());
+        // Removing a checkpoint that is not there is not an error
+        remove(&path).unwrap();
+        assert_eq!(checkpoint.simulation.settings, settings);
+        let resumed = simulator::continue_simulation(checkpoint.simulation, checkpoint.data, |_, _| Ok(()), |_, _| Ok(())).unwrap();
+
+        assert_eq!(resumed.len(), uninterrupted.len());
+        for (resumed, uninterrupted) in resumed.iter().zip(&uninterrupted) {
+            assert_eq!(resumed.len(), uninterrupted.len());
+            for (resumed, uninterrupted) in resumed.iter().zip(uninterrupted) {
+                assert_eq!(resumed.time.to_bits(), uninterrupted.time.to_bits());
+                assert_eq!(resumed.latitude.to_bits(), uninterrupted.latitude.to_bits());
+                assert_eq!(resumed.longitude.to_bits(), uninterrupted.longitude.to_bits());
+                assert_eq!(resumed.battery_charge.to_bits(), uninterrupted.battery_charge.to_bits());
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::mpsc;
+
+use crate::{error, message_passers, precision, progress, settings, simulator};
+
+/// What Marco has to achieve for a velocity to count as fast enough.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Criterion {
+    /// The star never sets for Marco during the whole simulation.
+    #[default]
+    PermanentDaylight,
+    /// Marco gets to one of the poles (closer than `POLE_DISTANCE`) at some point of the simulation.
+    ReachPole,
+}
+
+impl Criterion {
+    pub const ALL: [Self; 2] = [Self::PermanentDaylight, Self::ReachPole];
+}
+
+impl AsRef<str> for Criterion {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::PermanentDaylight => "Stay in permanent daylight",
+            Self::ReachPole => "Reach a pole",
+        }
+    }
+}
+
+/// How close to a pole (deg) Marco has to get to count as having reached it.
+pub const POLE_DISTANCE: f64 = 0.5;
+
+/// The most times the velocity interval is halved, more would only reach below the precision of `f64`.
+const MAX_BISECTIONS: usize = 64;
+
+/// Parameters of the search for the critical velocity at different starting latitudes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Search {
+    pub criterion: Criterion,
+    /// The starting latitudes (deg) are spread evenly from the minimum to the maximum latitude.
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub latitudes_count: usize,
+    /// The fastest velocity (m/s) to try, if even this one is not enough, the latitude has no critical velocity.
+    pub max_velocity: f64,
+    /// The search stops when the critical velocity (m/s) is known at least this precisely.
+    pub tolerance: f64,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            criterion: Criterion::default(),
+            min_latitude: -89.0,
+            max_latitude: 89.0,
+            latitudes_count: 50,
+            max_velocity: 500.0,
+            tolerance: 0.01,
+        }
+    }
+}
+
+impl Search {
+    pub fn latitudes(&self) -> Vec<f64> {
+        settings::spread(self.min_latitude, self.max_latitude, self.latitudes_count.max(1))
+    }
+
+    pub fn validate(&self) -> Result<(), error::Error> {
+        for (value, name) in [(self.max_velocity, "The maximum velocity"), (self.tolerance, "The tolerance")] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(error::Error::InvalidSettings(format!("{name} of the search has to be positive and finite, but it is {value}")));
+            }
+        }
+        if self.latitudes_count == 0 {
+            return Err(error::Error::InvalidSettings(String::from("The search needs at least one latitude")));
+        }
+        Ok(())
+    }
+
+    /// The parameters of the search as keys and values, as they are written into the manifest of the exported critical velocities.
+    pub fn to_key_values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("search_criterion", format!("{:?}", self.criterion)),
+            ("search_min_latitude", self.min_latitude.to_string()),
+            ("search_max_latitude", self.max_latitude.to_string()),
+            ("search_latitudes_count", self.latitudes_count.to_string()),
+            ("search_max_velocity", self.max_velocity.to_string()),
+            ("search_tolerance", self.tolerance.to_string()),
+        ]
+    }
+}
+
+/// Simulates a single Marco walking at `velocity` (m/s) from `latitude` (deg) and checks whether he meets the criterion.
+pub fn meets_criterion(settings: &settings::Settings, latitude: f64, velocity: f64, criterion: Criterion) -> bool {
+    let settings = settings::Settings {
+        velocities_count: 1,
+        marco_min_velocity: velocity,
+        marco_max_velocity: velocity,
+        start_lat: latitude.to_radians(),
+        start_positions: settings::StartPositions::Single,
+        ..settings.clone()
+    };
+    match settings.precision {
+        precision::Precision::Single => simulation_meets_criterion(simulator::Simulation::<f32>::new(settings), criterion),
+        precision::Precision::Double => simulation_meets_criterion(simulator::Simulation::<f64>::new(settings), criterion),
+        precision::Precision::DoubleDouble => simulation_meets_criterion(simulator::Simulation::<precision::DoubleDouble>::new(settings), criterion),
+    }
+}
+
+fn simulation_meets_criterion<T: precision::Float>(mut simulation: simulator::Simulation<T>, criterion: Criterion) -> bool {
+    let pole_z = (90.0 - POLE_DISTANCE).to_radians().sin() * simulation.settings.planet_radius;
+    loop {
+        match criterion {
+            Criterion::PermanentDaylight if !simulation.is_in_daylight(0) => return false,
+            Criterion::ReachPole if simulation.position(0).z.abs() >= pole_z => return true,
+            _ => {}
+        }
+        if simulation.is_finished() {
+            return criterion == Criterion::PermanentDaylight;
+        }
+        simulation.step();
+    }
+}
+
+/// Finds the slowest velocity (m/s) at which Marco starting at `latitude` (deg) meets the criterion, using bisection.
+/// This assumes that walking faster never hurts. Returns `None` if even the maximum velocity of the search is not enough.
+pub fn critical_velocity(settings: &settings::Settings, latitude: f64, search: &Search) -> Option<f64> {
+    if meets_criterion(settings, latitude, 0.0, search.criterion) {
+        return Some(0.0);
+    }
+    if !meets_criterion(settings, latitude, search.max_velocity, search.criterion) {
+        return None;
+    }
+    let (mut too_slow, mut fast_enough) = (0.0, search.max_velocity);
+    for _ in 0..MAX_BISECTIONS {
+        if fast_enough - too_slow <= search.tolerance {
+            break;
+        }
+        let velocity = (too_slow + fast_enough) / 2.0;
+        if meets_criterion(settings, latitude, velocity, search.criterion) {
+            fast_enough = velocity;
+        } else {
+            too_slow = velocity;
+        }
+    }
+    Some(fast_enough)
+}
+
+/// Finds the critical velocity for every latitude of the search, calling `on_result` with the latitude (deg) and its critical velocity (m/s) as soon as it is known.
+pub fn critical_velocity_curve(
+    settings: &settings::Settings,
+    search: &Search,
+    mut on_result: impl FnMut(f64, Option<f64>) -> Result<(), error::Error>,
+) -> Result<Vec<(f64, Option<f64>)>, error::Error> {
+    let mut curve = Vec::with_capacity(search.latitudes_count);
+    for latitude in search.latitudes() {
+        let velocity = critical_velocity(settings, latitude, search);
+        on_result(latitude, velocity)?;
+        curve.push((latitude, velocity));
+    }
+    Ok(curve)
+}
+
+pub fn recalculate_critical_velocities(settings: settings::Settings, search: Search, sender: mpsc::SyncSender<message_passers::Message>) {
+    let result = run_search(&settings, &search, &sender);
+    message_passers::finish_calculation(&sender, result);
+}
+
+fn run_search(settings: &settings::Settings, search: &Search, sender: &mpsc::SyncSender<message_passers::Message>) -> Result<(), error::Error> {
+    settings.validate()?;
+    search.validate()?;
+    sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::CriticalVelocities))?;
+    let mut reporter = progress::ProgressReporter::new();
+    let mut done = 0;
+    critical_velocity_curve(settings, search, |latitude, velocity| {
+        sender.send(message_passers::Message::CriticalVelocity(latitude, velocity))?;
+        done += 1;
+        if let Some(progress) = reporter.step(done as f64, search.latitudes_count as f64) {
+            sender.send(message_passers::Message::Progress(progress))?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn critical_velocity_is_the_slowest_that_meets_the_criterion() {
+        let settings = settings::Settings {
+            generate_image: false,
+            velocities_count: 1,
+            planet_radius: 100.0,
+            timestep: 60.0,
+            simulation_time: 86400.0,
+            ..Default::default()
+        };
+        let search = Search {
+            tolerance: 0.05,
+            ..Default::default()
+        };
+        let velocity = critical_velocity(&settings, 30.0, &search).expect("the maximum velocity should be enough");
+        assert!(velocity > 0.0);
+        assert!(meets_criterion(&settings, 30.0, velocity, search.criterion));
+        assert!(!meets_criterion(&settings, 30.0, velocity - search.tolerance, search.criterion));
+
+        // A tolerance below the precision of the velocities still ends the search
+        let search = Search { tolerance: 1e-300, ..search };
+        assert!(search.validate().is_ok());
+        assert!(critical_velocity(&settings, 30.0, &search).is_some());
+        assert!(Search { tolerance: 0.0, ..search }.validate().is_err());
+        assert!(Search {
+            max_velocity: f64::INFINITY,
+            ..search
+        }
+        .validate()
+        .is_err());
+        assert!(Search { latitudes_count: 0, ..search }.validate().is_err());
+    }
+}
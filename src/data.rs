@@ -1,19 +1,73 @@
 use nalgebra::Vector3;
 
+/// The colour of a series of points, stored as non-premultiplied RGBA.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Colour {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Colour {
+    pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl From<Colour> for eframe::egui::Color32 {
+    fn from(colour: Colour) -> Self {
+        Self::from_rgba_unmultiplied(colour.r, colour.g, colour.b, colour.a)
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Data {
+    /// The index of the series (a combination of a starting position and a velocity) the point belongs to.
+    pub series: usize,
     pub latitude: f64,
     pub longitude: f64,
     pub time: f64,
-    pub colour: eframe::egui::Color32,
+    pub velocity: f64,
+    /// The speed (m/s) Marco moved at during the last step, which differs from the velocity at night and with the rover energy model.
+    pub speed: f64,
+    /// The fraction of the battery capacity left, always 1 without the rover energy model.
+    pub battery_charge: f64,
+    /// The angular distance from Marco to the subsolar point (deg).
+    pub subsolar_distance: f64,
+    /// The initial bearing of the great circle from Marco to the subsolar point, measured clockwise from north (deg).
+    pub subsolar_bearing: f64,
+    /// The latitude of the subsolar point, which is the declination of the star (deg).
+    pub subsolar_latitude: f64,
+    /// The longitude of the subsolar point (deg).
+    pub subsolar_longitude: f64,
+    pub colour: Colour,
 }
 
 impl Data {
-    pub fn new(latitude: f64, longitude: f64, time: f64, colour: eframe::egui::Color32) -> Self {
-        Self { latitude, longitude, time, colour }
-    }
+    pub fn from_raw(marco_pos: Vector3<f64>, sun_pos_norm: Vector3<f64>, time: f64, planet_radius: f64, velocity: f64, colour: Colour) -> Self {
+        let marco_pos_norm = marco_pos.normalize();
+        let subsolar_distance = marco_pos_norm.dot(&sun_pos_norm).clamp(-1.0, 1.0).acos();
+
+        // The east and north directions are scaled by the cosine of the latitude, which does not change the bearing and avoids dividing by zero at the poles
+        let east = Vector3::new(0.0, 0.0, 1.0).cross(&marco_pos_norm);
+        let north = marco_pos_norm.cross(&east);
+        let subsolar_bearing = sun_pos_norm.dot(&east).atan2(sun_pos_norm.dot(&north)).to_degrees().rem_euclid(360.0);
 
-    pub fn from_raw(marco_pos: Vector3<f64>, time: f64, planet_radius: f64, colour: eframe::egui::Color32) -> Self {
-        Self::new((marco_pos.z / planet_radius).asin().to_degrees(), marco_pos.y.atan2(marco_pos.x).to_degrees(), time, colour)
+        Self {
+            series: 0,
+            latitude: (marco_pos.z / planet_radius).asin().to_degrees(),
+            longitude: marco_pos.y.atan2(marco_pos.x).to_degrees(),
+            time,
+            velocity,
+            speed: 0.0,
+            battery_charge: 1.0,
+            subsolar_distance: subsolar_distance.to_degrees(),
+            subsolar_bearing,
+            subsolar_latitude: sun_pos_norm.z.clamp(-1.0, 1.0).asin().to_degrees(),
+            subsolar_longitude: sun_pos_norm.y.atan2(sun_pos_norm.x).to_degrees(),
+            colour,
+        }
     }
 }
@@ -0,0 +1,108 @@
+use std::f64::consts::PI;
+
+use nalgebra::Vector3;
+
+/// What Marco does while the moon covers part of the star.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EclipseBehaviour {
+    /// Marco stops while any part of the star is covered.
+    #[default]
+    Stop,
+    /// Marco walks at his velocity times the uncovered fraction of the star.
+    /// A solar-powered rover is slowed by the lower power of its panels instead.
+    Slow,
+}
+
+impl EclipseBehaviour {
+    pub const ALL: [Self; 2] = [Self::Stop, Self::Slow];
+}
+
+impl AsRef<str> for EclipseBehaviour {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::Stop => "Stop",
+            Self::Slow => "Slow down",
+        }
+    }
+}
+
+/// An eclipse of the star as seen by one Marco, from when the moon starts covering the star above his horizon until it stops.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Eclipse {
+    pub series: usize,
+    /// The times (s) of the first and the last step the star was partially covered.
+    pub start: f64,
+    pub end: f64,
+    /// The largest fraction of the disc of the star that was covered.
+    pub max_coverage: f64,
+}
+
+/// The fraction of the disc of the star covered by the disc of the moon, given their angular radii and the angular distance of their centres (rad).
+/// The discs are small, so they are treated as flat.
+pub fn covered_fraction(star_radius: f64, moon_radius: f64, distance: f64) -> f64 {
+    if distance >= star_radius + moon_radius {
+        return 0.0;
+    }
+    if distance <= moon_radius - star_radius {
+        return 1.0;
+    }
+    // An annular eclipse
+    if distance <= star_radius - moon_radius {
+        return (moon_radius / star_radius).powi(2);
+    }
+    // The area of the lens where the discs overlap, from the angles (seen from the centres of the discs) of the arcs bounding it
+    let star_angle_cos = (distance.powi(2) + star_radius.powi(2) - moon_radius.powi(2)) / (2.0 * distance * star_radius);
+    let moon_angle_cos = (distance.powi(2) + moon_radius.powi(2) - star_radius.powi(2)) / (2.0 * distance * moon_radius);
+    let star_part = star_radius.powi(2) * star_angle_cos.clamp(-1.0, 1.0).acos();
+    let moon_part = moon_radius.powi(2) * moon_angle_cos.clamp(-1.0, 1.0).acos();
+    let kite = 0.5
+        * ((-distance + star_radius + moon_radius) * (distance + star_radius - moon_radius) * (distance - star_radius + moon_radius) * (distance + star_radius + moon_radius))
+            .max(0.0)
+            .sqrt();
+    ((star_part + moon_part - kite) / (PI * star_radius.powi(2))).clamp(0.0, 1.0)
+}
+
+/// Whether the line from `position` (km) towards the star, in the direction `star_direction`, passes through the plane of the equator between `inner_radius` and `outer_radius` (km).
+pub fn is_in_ring_shadow(position: &Vector3<f64>, star_direction: &Vector3<f64>, inner_radius: f64, outer_radius: f64) -> bool {
+    // The ring plane is only crossed on the way to the star when the star is on its other side
+    if position.z * star_direction.z >= 0.0 {
+        return false;
+    }
+    let crossing = position + star_direction * (-position.z / star_direction.z);
+    (inner_radius..=outer_radius).contains(&crossing.xy().norm())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn covered_fraction_of_partial_total_and_annular_eclipses() {
+        assert_eq!(covered_fraction(1.0, 1.0, 2.5), 0.0);
+        assert_eq!(covered_fraction(1.0, 1.1, 0.05), 1.0);
+        assert!((covered_fraction(1.0, 0.5, 0.2) - 0.25).abs() < 1e-12);
+        // Two equal discs with their centres one radius apart overlap in two circular segments of 120 deg
+        let expected = 2.0 * (PI / 3.0 - 3.0_f64.sqrt() / 4.0) / PI;
+        assert!((covered_fraction(1.0, 1.0, 1.0) - expected).abs() < 1e-12);
+        // The covered fraction grows continuously as the moon moves in front of the star
+        let fractions = (0..=100).map(|i| covered_fraction(1.0, 0.9, 2.0 - i as f64 * 0.02)).collect::<Vec<f64>>();
+        assert!(fractions.windows(2).all(|pair| pair[1] >= pair[0]));
+    }
+
+    #[test]
+    fn ring_shadow_falls_on_the_winter_hemisphere() {
+        let star = Vector3::new(1.0, 0.0, 0.4).normalize();
+        let (inner, outer) = (7700.0, 14100.0);
+        let at = |latitude: f64| {
+            let latitude = latitude.to_radians();
+            Vector3::new(latitude.cos(), 0.0, latitude.sin()) * 6000.0
+        };
+        assert!(is_in_ring_shadow(&at(-30.0), &star, inner, outer));
+        assert!(!is_in_ring_shadow(&at(30.0), &star, inner, outer));
+        // Close to the equator the line to the star passes inside the rings, far from it outside them
+        assert!(!is_in_ring_shadow(&at(-1.0), &star, inner, outer));
+        assert!(!is_in_ring_shadow(&at(-60.0), &star, inner, outer));
+        // At an equinox the star is in the ring plane and the rings cast no shadow
+        assert!(!is_in_ring_shadow(&at(-30.0), &Vector3::new(1.0, 0.0, 0.0), inner, outer));
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+use std::sync::mpsc;
+
+#[derive(Debug)]
+pub enum Error {
+    /// A message could not be sent to the other thread, usually because the application is closing.
+    Send(String),
+    Io(std::io::Error),
+    Rendering(String),
+    InvalidSettings(String),
+    Terrain(String),
+    InvalidCheckpoint(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Send(err) => write!(f, "Failed to send a message: {err}"),
+            Self::Io(err) => write!(f, "Input/output error: {err}"),
+            Self::Rendering(err) => write!(f, "Failed to render the image: {err}"),
+            Self::InvalidSettings(err) => write!(f, "Invalid settings: {err}"),
+            Self::Terrain(err) => write!(f, "Failed to load the terrain mask: {err}"),
+            Self::InvalidCheckpoint(err) => write!(f, "Invalid checkpoint: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl<T> From<mpsc::SendError<T>> for Error {
+    fn from(err: mpsc::SendError<T>) -> Self {
+        Self::Send(err.to_string())
+    }
+}
+
+#[cfg(feature = "image")]
+impl<E: std::error::Error + Send + Sync> From<plotters::drawing::DrawingAreaErrorKind<E>> for Error {
+    fn from(err: plotters::drawing::DrawingAreaErrorKind<E>) -> Self {
+        Self::Rendering(err.to_string())
+    }
+}
+
+#[cfg(feature = "terrain")]
+impl From<png::DecodingError> for Error {
+    fn from(err: png::DecodingError) -> Self {
+        Self::Terrain(err.to_string())
+    }
+}
@@ -0,0 +1,68 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::{data, eclipse, error};
+
+/// Writes the points of all series into a CSV file, one row per point.
+pub fn write_csv(path: &Path, series: &[&Vec<data::Data>]) -> Result<(), error::Error> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(
+        writer,
+        "series,velocity_m_s,time_s,latitude_deg,longitude_deg,subsolar_distance_deg,subsolar_bearing_deg,subsolar_latitude_deg,subsolar_longitude_deg,speed_m_s,battery_charge"
+    )?;
+    for points in series {
+        for point in points.iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{}",
+                point.series,
+                point.velocity,
+                point.time,
+                point.latitude,
+                point.longitude,
+                point.subsolar_distance,
+                point.subsolar_bearing,
+                point.subsolar_latitude,
+                point.subsolar_longitude,
+                point.speed,
+                point.battery_charge
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the critical velocity (m/s) for every starting latitude (deg) into a CSV file, leaving the velocity empty where there is none.
+pub fn write_critical_velocity_csv(path: &Path, curve: &[(f64, Option<f64>)]) -> Result<(), error::Error> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(writer, "start_latitude_deg,critical_velocity_m_s")?;
+    for (latitude, velocity) in curve {
+        match velocity {
+            Some(velocity) => writeln!(writer, "{latitude},{velocity}")?,
+            None => writeln!(writer, "{latitude},")?,
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the eclipses into a CSV file, one row per eclipse.
+pub fn write_eclipse_csv(path: &Path, eclipses: &[eclipse::Eclipse]) -> Result<(), error::Error> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writeln!(writer, "series,start_s,end_s,max_coverage")?;
+    for eclipse in eclipses {
+        writeln!(writer, "{},{},{},{}", eclipse.series, eclipse.start, eclipse.end, eclipse.max_coverage)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
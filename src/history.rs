@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::{data, runs, settings};
+
+/// Undo and redo stacks for the settings.
+/// Changes done while a value is being dragged or typed in are merged into a single edit.
+#[derive(Default)]
+pub struct SettingsHistory {
+    undo: Vec<settings::Settings>,
+    redo: Vec<settings::Settings>,
+    edit_start: Option<settings::Settings>,
+}
+
+impl SettingsHistory {
+    /// Records that the settings were changed, `before` being the settings before the change.
+    pub fn record_change(&mut self, before: settings::Settings) {
+        if self.edit_start.is_none() {
+            self.edit_start = Some(before);
+        }
+    }
+
+    /// Ends the edit in progress, if there is any, making it a single undo step.
+    pub fn finish_edit(&mut self, current: &settings::Settings) {
+        if let Some(before) = self.edit_start.take() {
+            if before != *current {
+                self.undo.push(before);
+                self.redo.clear();
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.edit_start.is_some()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Returns whether there was anything to undo.
+    pub fn undo(&mut self, current: &mut settings::Settings) -> bool {
+        self.finish_edit(current);
+        match self.undo.pop() {
+            Some(previous) => {
+                self.redo.push(std::mem::replace(current, previous));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns whether there was anything to redo.
+    pub fn redo(&mut self, current: &mut settings::Settings) -> bool {
+        self.finish_edit(current);
+        match self.redo.pop() {
+            Some(next) => {
+                self.undo.push(std::mem::replace(current, next));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the settings as a single undoable edit.
+    pub fn replace(&mut self, current: &mut settings::Settings, new: settings::Settings) {
+        self.finish_edit(current);
+        if *current != new {
+            self.undo.push(std::mem::replace(current, new));
+            self.redo.clear();
+        }
+    }
+}
+
+/// A record of a calculation that was started.
+pub struct RunLogEntry {
+    pub settings: settings::Settings,
+    pub started: Instant,
+    /// `None` while the calculation is running.
+    pub duration: Option<Duration>,
+    pub headline: String,
+}
+
+impl RunLogEntry {
+    pub fn new(settings: settings::Settings) -> Self {
+        Self {
+            settings,
+            started: Instant::now(),
+            duration: None,
+            headline: String::new(),
+        }
+    }
+
+    pub fn finish(&mut self, data: &HashMap<usize, Vec<data::Data>>) {
+        self.duration = Some(self.started.elapsed());
+        self.headline = headline(data);
+    }
+}
+
+/// Summarises the final positions of Marco in a run.
+pub fn headline(data: &HashMap<usize, Vec<data::Data>>) -> String {
+    let final_points = runs::ordered_series(data).into_iter().filter_map(|series| series.last()).collect::<Vec<&data::Data>>();
+    match final_points.as_slice() {
+        [] => String::from("No points"),
+        [point] => format!("Final position: {:.3} deg latitude, {:.3} deg longitude", point.latitude, point.longitude),
+        points => {
+            let min_latitude = points.iter().map(|point| point.latitude).fold(f64::INFINITY, f64::min);
+            let max_latitude = points.iter().map(|point| point.latitude).fold(f64::NEG_INFINITY, f64::max);
+            format!("Final latitudes between {min_latitude:.3} deg and {max_latitude:.3} deg ({} velocities)", points.len())
+        }
+    }
+}
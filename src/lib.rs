@@ -0,0 +1,21 @@
+pub mod checkpoint;
+pub mod critical_velocity;
+pub mod data;
+pub mod eclipse;
+pub mod error;
+pub mod export;
+pub mod manifest;
+pub mod message_passers;
+#[cfg(feature = "image")]
+pub mod path_image;
+pub mod precision;
+pub mod progress;
+pub mod settings;
+pub mod simulator;
+pub mod solar_position;
+pub mod summary;
+pub mod terrain;
+pub mod time_unit;
+
+/// The directory all the outputs (images and exported data) are written into.
+pub const OUTPUT_DIRECTORY: &str = "plotters-doc-data";
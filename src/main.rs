@@ -1,14 +1,69 @@
 // #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 pub mod application;
-pub mod data;
-pub mod message_passers;
+pub mod history;
 pub mod rendering;
-pub mod settings;
-pub mod simulator;
+pub mod runs;
+
+pub use marco_on_a_desert_planet::{checkpoint, critical_velocity, data, eclipse, error, export, manifest, message_passers, precision, progress, settings, simulator, solar_position, summary, terrain, time_unit};
 
 fn main() -> Result<(), eframe::Error> {
     env_logger::init();
 
+    let args = std::env::args().collect::<Vec<String>>();
+    if let Some(position) = args.iter().position(|arg| arg == "--resume") {
+        let path = args.get(position + 1).map(std::path::PathBuf::from);
+        let path = path.unwrap_or_else(|| std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join(checkpoint::CHECKPOINT_FILE_NAME));
+        if let Err(err) = resume_without_window(&path) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     eframe::run_native("Marco on a desert planet", Default::default(), Box::new(|cc| Box::new(application::Application::new(cc))))
 }
+
+/// Continues the run saved in the checkpoint without opening the window and exports its points, for long runs on machines without a display.
+fn resume_without_window(path: &std::path::Path) -> Result<(), error::Error> {
+    let started = std::time::Instant::now();
+    let checkpoint = checkpoint::read(path)?;
+    let settings = checkpoint.simulation.settings.clone();
+    let (sender, receiver) = std::sync::mpsc::sync_channel(message_passers::CALCULATOR_TO_MAIN_CAPACITY);
+    std::thread::spawn(move || simulator::resume_simulation(checkpoint, sender));
+
+    let mut data = std::collections::HashMap::<usize, Vec<data::Data>>::new();
+    let mut eclipses = Vec::new();
+    for message in receiver {
+        match message {
+            message_passers::Message::NewPoints(batch) => {
+                for points in batch {
+                    if let Some(first) = points.first() {
+                        data.entry(first.series).or_default().extend(points);
+                    }
+                }
+            }
+            message_passers::Message::NewStage(stage) => println!("{}", stage.as_ref()),
+            message_passers::Message::Progress(progress) => {
+                let eta = progress.eta.map(|eta| format!(", ~{} left", progress::format_duration(eta))).unwrap_or_default();
+                println!("{:.3}%{eta}", progress.fraction() * 100.0);
+            }
+            message_passers::Message::CriticalVelocity(..) => {}
+            message_passers::Message::Eclipse(eclipse) => eclipses.push(eclipse),
+            message_passers::Message::ImageSaved(path) => println!("The path image has been saved to {}", path.display()),
+            message_passers::Message::Error(err) => return Err(err),
+        }
+    }
+
+    let path = std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join("points.csv");
+    export::write_csv(&path, &runs::ordered_series(&data))?;
+    manifest::write(&path, &settings, &[("resumed_from_checkpoint", String::from("true"))], Some(started.elapsed()))?;
+    println!("The points have been exported to {}", path.display());
+    if settings.moon {
+        let path = std::path::Path::new(marco_on_a_desert_planet::OUTPUT_DIRECTORY).join("eclipses.csv");
+        export::write_eclipse_csv(&path, &eclipses)?;
+        manifest::write(&path, &settings, &[("resumed_from_checkpoint", String::from("true"))], Some(started.elapsed()))?;
+        println!("The eclipses have been exported to {}", path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::{error, settings};
+
+/// The first line of a manifest file, which changes whenever the format does.
+const HEADER: &str = "# Marco on a desert planet run manifest, version 1";
+
+/// The path of the manifest describing the output, next to it, so `3d-plot.png` is described by `3d-plot.manifest.txt`.
+pub fn manifest_path(output: &Path) -> PathBuf {
+    output.with_extension("manifest.txt")
+}
+
+/// Writes a manifest next to the output recording everything needed to produce it again: the settings, the version of the program and the platform.
+/// The manifest also contains a hash of the output, so it can be checked that a file is really the one the manifest describes.
+/// `parameters` are the parameters of the run that are not part of the settings, such as those of a critical-velocity search.
+/// Returns the path of the manifest.
+pub fn write(output: &Path, settings: &settings::Settings, parameters: &[(&str, String)], duration: Option<Duration>) -> Result<PathBuf, error::Error> {
+    let path = manifest_path(output);
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+    writeln!(writer, "{HEADER}")?;
+    writeln!(writer, "output = {}", output.file_name().unwrap_or(output.as_os_str()).to_string_lossy())?;
+    writeln!(writer, "output_fnv1a64 = {:016x}", hash_file(output)?)?;
+    writeln!(writer, "version = {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(writer, "git_revision = {}", option_env!("MARCO_GIT_REVISION").unwrap_or("unknown"))?;
+    writeln!(writer, "platform = {} {}", std::env::consts::OS, std::env::consts::ARCH)?;
+    match duration {
+        Some(duration) => writeln!(writer, "duration_s = {}", duration.as_secs_f64())?,
+        None => writeln!(writer, "duration_s = unknown")?,
+    }
+    for (key, value) in parameters {
+        writeln!(writer, "{key} = {value}")?;
+    }
+
+    // The same format as in checkpoints, so the settings can be read back with `Settings::from_key_values`
+    writeln!(writer, "[settings]")?;
+    for (key, value) in settings.to_key_values() {
+        writeln!(writer, "{key} = {value}")?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// The 64-bit FNV-1a hash of the contents of the file.
+pub fn hash_file(path: &Path) -> Result<u64, error::Error> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    let mut buffer = [0; 8192];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hash);
+        }
+        for &byte in &buffer[..read] {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_records_the_settings_and_the_hash_of_the_output() {
+        let output = std::env::temp_dir().join(format!("marco_manifest_test_{}.csv", std::process::id()));
+        std::fs::write(&output, "a").unwrap();
+        let settings = settings::Settings {
+            velocities_count: 7,
+            ..Default::default()
+        };
+        let path = write(&output, &settings, &[("resumed_from_checkpoint", String::from("false"))], Some(Duration::from_millis(1500))).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&output).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The FNV-1a hash of "a"
+        assert!(contents.contains("output_fnv1a64 = af63dc4c8601ec8c\n"));
+        assert!(contents.contains("duration_s = 1.5\n"));
+        assert!(contents.contains("resumed_from_checkpoint = false\n"));
+        let (_, settings_section) = contents.split_once("[settings]\n").unwrap();
+        let read_settings = settings::Settings::from_key_values(settings_section.lines().map(|line| line.split_once(" = ").unwrap_or((line.trim_end_matches(" ="), "")))).unwrap();
+        assert_eq!(read_settings, settings);
+    }
+}
@@ -1,17 +1,20 @@
 use std::sync::mpsc;
 
-use crate::data;
+use crate::{data, eclipse, error, progress};
+
+/// How many messages can wait in the channel from the calculator before the calculator has to wait for the GUI to catch up.
+pub const CALCULATOR_TO_MAIN_CAPACITY: usize = 64;
 
 pub struct MessagePassers {
     pub main_to_calculator_sender: mpsc::Sender<Message>,
-    pub calculator_to_main_sender: mpsc::Sender<Message>,
+    pub calculator_to_main_sender: mpsc::SyncSender<Message>,
     pub calculator_to_main_receiver: mpsc::Receiver<Message>,
 }
 
 impl Default for MessagePassers {
     fn default() -> Self {
         let (main_to_calculator_sender, _) = mpsc::channel();
-        let (calculator_to_main_sender, calculator_to_main_receiver) = mpsc::channel();
+        let (calculator_to_main_sender, calculator_to_main_receiver) = mpsc::sync_channel(CALCULATOR_TO_MAIN_CAPACITY);
         Self {
             main_to_calculator_sender,
 
@@ -23,7 +26,16 @@ impl Default for MessagePassers {
 
 pub enum Message {
     NewStage(CalculationStage),
-    NewPoint(data::Data),
+    /// Newly sampled points, the inner vectors each containing points of a single velocity.
+    NewPoints(Vec<Vec<data::Data>>),
+    Progress(progress::Progress),
+    /// The critical velocity (m/s) for a starting latitude (deg), if there is one.
+    CriticalVelocity(f64, Option<f64>),
+    /// An eclipse that has ended.
+    Eclipse(eclipse::Eclipse),
+    /// The path image has been saved to this path.
+    ImageSaved(std::path::PathBuf),
+    Error(error::Error),
 }
 
 #[derive(PartialEq, Eq)]
@@ -31,6 +43,7 @@ pub enum CalculationStage {
     Start,
     Points,
     Plots,
+    CriticalVelocities,
     End,
 }
 
@@ -40,7 +53,17 @@ impl AsRef<str> for CalculationStage {
             Self::Start => "Just started calculation",
             Self::Points => "Generating points",
             Self::Plots => "Generating plots",
+            Self::CriticalVelocities => "Searching for critical velocities",
             Self::End => "Ended calculations",
         }
     }
 }
+
+/// Reports the result of a calculation to the GUI and tells it that the calculation ended.
+pub fn finish_calculation(sender: &mpsc::SyncSender<Message>, result: Result<(), error::Error>) {
+    if let Err(err) = result {
+        // If this fails the application is closing, so there is nobody left to tell about the error
+        let _ = sender.send(Message::Error(err));
+    }
+    let _ = sender.send(Message::NewStage(CalculationStage::End));
+}
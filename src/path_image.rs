@@ -0,0 +1,217 @@
+use nalgebra::Vector3;
+
+use crate::{data, error, settings};
+
+/// Draws the paths on the planet into an image, calling `on_progress` with the number of lines drawn so far and the total number of lines.
+/// If `sun_pos_norm` is given, the illumination of the planet by a star in that direction is drawn too.
+/// Returns the path of the image.
+pub fn generate_image(
+    data: Vec<Vec<data::Data>>,
+    settings: settings::Settings,
+    sun_pos_norm: Option<Vector3<f64>>,
+    mut on_progress: impl FnMut(f64, f64) -> Result<(), error::Error>,
+) -> Result<std::path::PathBuf, error::Error> {
+    use plotters::prelude::*;
+
+    let scale_factor = settings.image_scale_factor;
+    let font = "sans";
+
+    let planet_radius = settings.planet_radius;
+
+    let out_file_name = std::path::Path::new(crate::OUTPUT_DIRECTORY).join("3d-plot.png");
+    std::fs::create_dir_all(crate::OUTPUT_DIRECTORY)?;
+
+    let area = BitMapBackend::new(&out_file_name, ((1024.0 * scale_factor) as u32, (760.0 * scale_factor) as u32)).into_drawing_area();
+
+    area.fill(&WHITE)?;
+
+    let x_axis = (-(planet_radius * 1.1)..(planet_radius * 1.1)).step(planet_radius * 1.1 / 100.0);
+    let z_axis = (-(planet_radius * 1.1)..(planet_radius * 1.1)).step(planet_radius * 1.1 / 100.0);
+
+    let mut chart =
+        ChartBuilder::on(&area)
+            .caption("Marco on a desert planet", (font, 20.0 * scale_factor))
+            .build_cartesian_3d(x_axis.clone(), -(planet_radius * 1.1)..(planet_radius * 1.1), z_axis.clone())?;
+
+    chart.with_projection(|mut pb| {
+        pb.yaw = 0.5 + std::f64::consts::PI;
+        pb.scale = 0.9;
+        pb.into_matrix()
+    });
+
+    chart
+        .configure_axes()
+        .light_grid_style(ShapeStyle::from(BLACK.mix(0.15)).stroke_width(scale_factor as u32))
+        .max_light_lines(3)
+        .label_style((font, 10.0 * scale_factor))
+        .draw()?;
+
+    let lines_of_longitude_count = 2000;
+    let lines_count = (lines_of_longitude_count + data.len()) as f64;
+    {
+        // Draw the planet
+        let resolution_lines_of_longitude = 10_000; // planet_radius as i32;
+        let t = (0..=resolution_lines_of_longitude).map(|t| t as f64 / (resolution_lines_of_longitude as f64) * std::f64::consts::TAU);
+        let y = t.clone().map(|t| planet_radius * t.sin());
+        let z = t.map(|t| planet_radius * t.cos());
+        for i in 0..lines_of_longitude_count {
+            let lon = i as f64 / (lines_of_longitude_count as f64) * std::f64::consts::PI;
+            let x = z.clone().map(|z| z * lon.sin());
+            let z = z.clone().map(|z| z * lon.cos());
+            let iter = x.zip(y.clone()).zip(z).map(|((x, y), z)| (x, y, z));
+            chart.draw_series(LineSeries::new(iter, ShapeStyle::from(BLACK.mix(0.3)).stroke_width((scale_factor / 4.0).max(1.0) as u32)))?;
+            on_progress((i + 1) as f64, lines_count)?;
+        }
+    }
+
+    if let Some(terrain) = settings.terrain_mask.as_deref() {
+        // Draw the impassable terrain as strips along the lines of latitude
+        let to_chart = |latitude: f64, longitude: f64| {
+            let (lat, lon) = (latitude.to_radians(), longitude.to_radians());
+            (planet_radius * lat.cos() * lon.sin(), planet_radius * lat.sin(), planet_radius * lat.cos() * lon.cos())
+        };
+        let strips_per_row = 4;
+        for (min_latitude, max_latitude, min_longitude, max_longitude) in terrain.blocked_rectangles(180, 360) {
+            for strip in 0..strips_per_row {
+                let latitude = min_latitude + (max_latitude - min_latitude) * (strip as f64 + 0.5) / (strips_per_row as f64);
+                let points = (0..=20).map(|t| to_chart(latitude, min_longitude + (max_longitude - min_longitude) * (t as f64) / 20.0));
+                chart.draw_series(LineSeries::new(
+                    points,
+                    ShapeStyle::from(RGBColor(120, 80, 40).mix(0.6)).stroke_width((scale_factor / 2.0).max(1.0) as u32),
+                ))?;
+            }
+        }
+    }
+
+    if let Some(sun_pos_norm) = sun_pos_norm {
+        // Draw the illumination, using two directions perpendicular to the direction towards the star to draw circles around it
+        let helper = if sun_pos_norm.z.abs() < 0.9 { Vector3::new(0.0, 0.0, 1.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+        let u = sun_pos_norm.cross(&helper).normalize();
+        let v = sun_pos_norm.cross(&u);
+        let to_chart = |position: Vector3<f64>| (position.y, position.z, position.x);
+        let resolution = 1000;
+        // Points of a circle on the planet at the given angular distance from the point the star is in the nadir of
+        let circle = |angle: f64| {
+            (0..=resolution).map(move |t| {
+                let t = t as f64 / (resolution as f64) * std::f64::consts::TAU;
+                to_chart(planet_radius * (-sun_pos_norm * angle.cos() + (u * t.cos() + v * t.sin()) * angle.sin()))
+            })
+        };
+
+        // Shade the night side
+        let night_circles_count = 200;
+        for i in 0..night_circles_count {
+            let angle = i as f64 / (night_circles_count as f64) * std::f64::consts::FRAC_PI_2;
+            chart.draw_series(LineSeries::new(
+                circle(angle),
+                ShapeStyle::from(RGBColor(0, 0, 80).mix(0.3)).stroke_width((scale_factor / 4.0).max(1.0) as u32),
+            ))?;
+        }
+
+        // Draw the terminator
+        chart.draw_series(LineSeries::new(
+            circle(std::f64::consts::FRAC_PI_2),
+            ShapeStyle::from(RGBColor(200, 0, 0)).stroke_width(scale_factor.max(1.0) as u32),
+        ))?;
+
+        // Draw an arrow pointing towards the star
+        let arrow_start = planet_radius * sun_pos_norm;
+        let arrow_tip = 1.4 * planet_radius * sun_pos_norm;
+        let arrow_style = ShapeStyle::from(RGBColor(255, 165, 0)).stroke_width(scale_factor.max(1.0) as u32);
+        chart.draw_series(LineSeries::new([to_chart(arrow_start), to_chart(arrow_tip)], arrow_style))?;
+        for side in [-1.0, 1.0] {
+            let head_end = arrow_tip - 0.1 * planet_radius * sun_pos_norm + side * 0.05 * planet_radius * u;
+            chart.draw_series(LineSeries::new([to_chart(arrow_tip), to_chart(head_end)], arrow_style))?;
+        }
+    }
+
+    // Draw the paths
+    for i in (0..data.len()).rev() {
+        if data[i].is_empty() {
+            continue;
+        }
+        chart.draw_series(LineSeries::new(
+            (0..data[i].len()).map(|t| {
+                let point = &data[i][t];
+                let lat = point.latitude.to_radians();
+                let lon = point.longitude.to_radians();
+                let (x, y, z) = (planet_radius * lat.cos() * lon.cos(), planet_radius * lat.cos() * lon.sin(), planet_radius * lat.sin());
+                (y, z, x)
+            }),
+            ShapeStyle::from(RGBAColor(data[i][0].colour.r, data[i][0].colour.g, data[i][0].colour.b, data[i][0].colour.a as f64 / 255.0)),
+        ))?;
+        on_progress((lines_of_longitude_count + data.len() - i) as f64, lines_count)?;
+    }
+
+    // Draw the track of the subsolar point, which is the same for all the velocities
+    if let Some(points) = data.iter().find(|points| !points.is_empty()) {
+        chart.draw_series(DashedLineSeries::new(
+            points.iter().map(|point| {
+                let lat = point.subsolar_latitude.to_radians();
+                let lon = point.subsolar_longitude.to_radians();
+                let (x, y, z) = (planet_radius * lat.cos() * lon.cos(), planet_radius * lat.cos() * lon.sin(), planet_radius * lat.sin());
+                (y, z, x)
+            }),
+            10.0 * scale_factor,
+            5.0 * scale_factor,
+            ShapeStyle::from(RGBColor(255, 165, 0)).stroke_width((scale_factor / 2.0).max(1.0) as u32),
+        ))?;
+    }
+
+    /*chart
+        .draw_series(
+            SurfaceSeries::xoz(
+                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
+                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
+                |x, z| {
+                    let y_2 = radius * radius - (x * x + z * z);
+                    if y_2 < 0.0 {
+                        0.0
+                    } else {
+                        y_2.sqrt()
+                    }
+                },
+            )
+            .style(BLUE.mix(0.2).filled()),
+        )?
+        .label("Surface")
+        .legend(|(x, y)| Rectangle::new([(x + 5, y - 5), (x + 15, y + 5)], BLUE.mix(0.5).filled()));
+
+    chart
+        .draw_series(
+            SurfaceSeries::xoz(
+                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
+                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
+                |x, z| {
+                    let y_2 = radius * radius - (x * x + z * z);
+                    if y_2 < 0.0 {
+                        0.0
+                    } else {
+                        -y_2.sqrt()
+                    }
+                },
+            )
+            .style(BLUE.mix(0.2).filled()),
+        )?
+        .label("Surface")
+        .legend(|(x, y)| Rectangle::new([(x + 5, y - 5), (x + 15, y + 5)], BLUE.mix(0.5).filled()));*/
+
+    /*chart
+    .draw_series(LineSeries::new(
+        (-100..100)
+            .map(|y| y as f64 / 100.0 * (radius * 1.1))
+            .map(|y| ((radius * 1.1) * (y * 10.0).sin(), y, (radius * 1.1) * (y * 10.0).cos())),
+        &BLACK,
+    ))?
+    .label("Line")
+    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK));*/
+
+    chart.configure_series_labels().border_style(BLACK).draw()?;
+
+    // To avoid the IO failure being ignored silently, we manually call the present function
+    area.present()?;
+    // The drawing area borrows the path
+    drop(chart);
+    drop(area);
+    Ok(out_file_name)
+}
@@ -0,0 +1,342 @@
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+use nalgebra::Vector3;
+
+/// The floating point type the positions of Marco and the star are simulated in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Precision {
+    /// 32-bit floats, useful to see how much the results depend on the round-off.
+    Single,
+    #[default]
+    Double,
+    /// Double-double floats with about 32 significant digits, for verifying the round-off of double precision runs.
+    /// The simulation is many times slower.
+    DoubleDouble,
+}
+
+impl Precision {
+    pub const ALL: [Self; 3] = [Self::Single, Self::Double, Self::DoubleDouble];
+}
+
+impl AsRef<str> for Precision {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::Single => "Single (f32)",
+            Self::Double => "Double (f64)",
+            Self::DoubleDouble => "Double-double (~32 digits)",
+        }
+    }
+}
+
+/// The operations the simulation needs from its floating point type.
+pub trait Float:
+    nalgebra::Scalar + Copy + PartialOrd + Add<Output = Self> + AddAssign + Sub<Output = Self> + SubAssign + Mul<Output = Self> + MulAssign + Div<Output = Self> + DivAssign + Neg<Output = Self>
+{
+    fn from_f64(value: f64) -> Self;
+    /// Rounds the value to the nearest `f64`.
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+
+    fn zero() -> Self {
+        Self::from_f64(0.0)
+    }
+
+    fn pi() -> Self;
+}
+
+impl Float for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn sqrt(self) -> Self {
+        f32::sqrt(self)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        f32::sin_cos(self)
+    }
+
+    fn pi() -> Self {
+        std::f32::consts::PI
+    }
+}
+
+impl Float for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn sqrt(self) -> Self {
+        f64::sqrt(self)
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        f64::sin_cos(self)
+    }
+
+    fn pi() -> Self {
+        std::f64::consts::PI
+    }
+}
+
+pub fn dot<T: Float>(a: &Vector3<T>, b: &Vector3<T>) -> T {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
+pub fn norm<T: Float>(vector: &Vector3<T>) -> T {
+    dot(vector, vector).sqrt()
+}
+
+pub fn normalize<T: Float>(vector: &Vector3<T>) -> Vector3<T> {
+    vector / norm(vector)
+}
+
+/// A rotation stored as a unit quaternion, computed the same way as `nalgebra::UnitQuaternion` but in any `Float`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rotation<T: Float> {
+    scalar: T,
+    vector: Vector3<T>,
+}
+
+impl<T: Float> Rotation<T> {
+    /// The rotation around the direction of `axis_angle` by its length (rad).
+    pub fn new(axis_angle: Vector3<T>) -> Self {
+        let angle = norm(&axis_angle);
+        if angle <= T::zero() {
+            return Self {
+                scalar: T::from_f64(1.0),
+                vector: Vector3::new(T::zero(), T::zero(), T::zero()),
+            };
+        }
+        let (sin, cos) = (angle / T::from_f64(2.0)).sin_cos();
+        Self {
+            scalar: cos,
+            vector: axis_angle / angle * sin,
+        }
+    }
+
+    pub fn rotate(&self, vector: &Vector3<T>) -> Vector3<T> {
+        let t = self.vector.cross(vector) * T::from_f64(2.0);
+        let cross = self.vector.cross(&t);
+        t * self.scalar + cross + vector
+    }
+}
+
+/// A double-double float, the unevaluated sum of two `f64`s, `lo` being at most half an ulp of `hi`.
+/// The algorithms are those of Hida, Li and Bailey, "Library for double-double and quad-double arithmetic" (2007).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DoubleDouble {
+    pub hi: f64,
+    pub lo: f64,
+}
+
+impl DoubleDouble {
+    const HALF_PI: Self = Self {
+        hi: std::f64::consts::FRAC_PI_2,
+        lo: 6.123233995736766e-17,
+    };
+
+    pub const fn new(hi: f64, lo: f64) -> Self {
+        Self { hi, lo }
+    }
+
+    /// The exact sum of `a` and `b`.
+    fn two_sum(a: f64, b: f64) -> Self {
+        let sum = a + b;
+        let b_virtual = sum - a;
+        Self::new(sum, (a - (sum - b_virtual)) + (b - b_virtual))
+    }
+
+    /// The exact sum of `a` and `b`, only if `|a| >= |b|`.
+    fn quick_two_sum(a: f64, b: f64) -> Self {
+        let sum = a + b;
+        Self::new(sum, b - (sum - a))
+    }
+
+    /// The exact product of `a` and `b`.
+    fn two_prod(a: f64, b: f64) -> Self {
+        let product = a * b;
+        Self::new(product, a.mul_add(b, -product))
+    }
+
+    /// The Taylor series of the sine and the cosine, precise for `|x| <= PI / 4`.
+    fn sin_cos_taylor(x: Self) -> (Self, Self) {
+        let x_squared = x * x;
+        let (mut sin, mut term) = (x, x);
+        let mut n = 1.0;
+        while term.hi.abs() > 1e-34 {
+            term = -term * x_squared / Self::from_f64((n + 1.0) * (n + 2.0));
+            sin += term;
+            n += 2.0;
+        }
+        let (mut cos, mut term) = (Self::from_f64(1.0), Self::from_f64(1.0));
+        let mut n = 0.0;
+        while term.hi.abs() > 1e-34 {
+            term = -term * x_squared / Self::from_f64((n + 1.0) * (n + 2.0));
+            cos += term;
+            n += 2.0;
+        }
+        (sin, cos)
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let s = Self::two_sum(self.hi, other.hi);
+        let t = Self::two_sum(self.lo, other.lo);
+        let s = Self::quick_two_sum(s.hi, s.lo + t.hi);
+        Self::quick_two_sum(s.hi, s.lo + t.lo)
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let p = Self::two_prod(self.hi, other.hi);
+        Self::quick_two_sum(p.hi, p.lo + (self.hi * other.lo + self.lo * other.hi))
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let q1 = self.hi / other.hi;
+        let r = self - other * Self::from_f64(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * Self::from_f64(q2);
+        let q3 = r.hi / other.hi;
+        Self::quick_two_sum(q1, q2) + Self::from_f64(q3)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.hi, -self.lo)
+    }
+}
+
+impl AddAssign for DoubleDouble {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for DoubleDouble {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl MulAssign for DoubleDouble {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl DivAssign for DoubleDouble {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+impl PartialOrd for DoubleDouble {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match self.hi.partial_cmp(&other.hi) {
+            Some(Ordering::Equal) => self.lo.partial_cmp(&other.lo),
+            ordering => ordering,
+        }
+    }
+}
+
+impl Float for DoubleDouble {
+    fn from_f64(value: f64) -> Self {
+        Self::new(value, 0.0)
+    }
+
+    fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    fn sqrt(self) -> Self {
+        if self.hi <= 0.0 {
+            return Self::from_f64(self.hi.sqrt());
+        }
+        // One Newton step from the double precision square root doubles the number of correct digits
+        let x = 1.0 / self.hi.sqrt();
+        let ax = self.hi * x;
+        Self::two_sum(ax, (self - Self::two_prod(ax, ax)).hi * (x * 0.5))
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        // Reduce the angle into [-PI / 4, PI / 4] by multiples of PI / 2, which is precise enough for the small angles of the simulation
+        let quadrant = (self / Self::HALF_PI).hi.round();
+        let (sin, cos) = Self::sin_cos_taylor(self - Self::HALF_PI * Self::from_f64(quadrant));
+        match (quadrant as i64).rem_euclid(4) {
+            0 => (sin, cos),
+            1 => (cos, -sin),
+            2 => (-sin, -cos),
+            _ => (-cos, sin),
+        }
+    }
+
+    fn pi() -> Self {
+        Self::HALF_PI * Self::from_f64(2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: DoubleDouble, expected: DoubleDouble) {
+        let error = (actual - expected).hi.abs();
+        assert!(error <= 1e-30, "{actual:?} differs from {expected:?} by {error:?}");
+    }
+
+    #[test]
+    fn double_double_is_more_precise_than_double() {
+        let third = DoubleDouble::from_f64(1.0) / DoubleDouble::from_f64(3.0);
+        assert_close(third * DoubleDouble::from_f64(3.0), DoubleDouble::from_f64(1.0));
+        // 1/3 is not a double, so the low part holds the digits the double does not have
+        assert!(third.lo != 0.0);
+
+        let two = DoubleDouble::from_f64(2.0);
+        assert_close(two.sqrt() * two.sqrt(), two);
+
+        for angle in [1e-9, 0.3, 1.0, 2.5, -4.0, 10.0] {
+            let angle = DoubleDouble::from_f64(angle);
+            let (sin, cos) = angle.sin_cos();
+            assert_close(sin * sin + cos * cos, DoubleDouble::from_f64(1.0));
+            assert!((sin.to_f64() - angle.to_f64().sin()).abs() < 1e-15);
+            assert!((cos.to_f64() - angle.to_f64().cos()).abs() < 1e-15);
+        }
+        // sin(PI / 6) is exactly a half
+        let (sin, _) = (DoubleDouble::pi() / DoubleDouble::from_f64(6.0)).sin_cos();
+        assert_close(sin, DoubleDouble::from_f64(0.5));
+    }
+}
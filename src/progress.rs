@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+/// How often the progress is reported at most.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Progress of the current calculation stage.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// How much of the stage is done, in the units of the stage (simulated seconds when generating points, drawn lines when generating plots).
+    pub done: f64,
+    pub total: f64,
+    /// Steps (simulation steps or drawn lines) per second of wall-clock time.
+    pub steps_per_second: f64,
+    /// The estimated remaining wall-clock time, if it can be estimated yet.
+    pub eta: Option<Duration>,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f64 {
+        if self.total > 0.0 {
+            (self.done / self.total).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Measures the throughput of a stage and decides when to report its progress.
+pub struct ProgressReporter {
+    started: Instant,
+    last_report: Option<Instant>,
+    steps: u64,
+    /// How much of the stage was done before this reporter started, such as the part of a resumed simulation before its checkpoint.
+    done_before: f64,
+}
+
+impl Default for ProgressReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter {
+    pub fn new() -> Self {
+        Self::resumed(0.0)
+    }
+
+    /// A reporter for a stage of which `done_before` was already done in an earlier session, so the ETA only depends on the work done in this one.
+    pub fn resumed(done_before: f64) -> Self {
+        Self {
+            started: Instant::now(),
+            last_report: None,
+            steps: 0,
+            done_before,
+        }
+    }
+
+    /// Counts a step and returns the current progress if it is time to report it.
+    pub fn step(&mut self, done: f64, total: f64) -> Option<Progress> {
+        self.steps += 1;
+        let now = Instant::now();
+        if self.last_report.is_some_and(|last_report| now.duration_since(last_report) < REPORT_INTERVAL) {
+            return None;
+        }
+        self.last_report = Some(now);
+        Some(self.progress(done, total))
+    }
+
+    pub fn progress(&self, done: f64, total: f64) -> Progress {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let done_now = done - self.done_before;
+        let eta = if done_now > 0.0 && elapsed > 0.0 {
+            Duration::try_from_secs_f64(elapsed * ((total - done) / done_now).max(0.0)).ok()
+        } else {
+            None
+        };
+        Progress {
+            done,
+            total,
+            steps_per_second: if elapsed > 0.0 { self.steps as f64 / elapsed } else { 0.0 },
+            eta,
+        }
+    }
+}
+
+/// Formats a duration as hours, minutes and seconds, leaving out the leading zero parts.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours} h {minutes:02} min {seconds:02} s")
+    } else if minutes > 0 {
+        format!("{minutes} min {seconds:02} s")
+    } else {
+        format!("{seconds} s")
+    }
+}
@@ -1 +1,5 @@
+pub mod notifications;
+pub mod start_picker;
+pub mod terrain;
+pub mod time_graph;
 pub mod windows;
@@ -0,0 +1,53 @@
+use eframe::egui;
+
+use crate::{application, time_unit};
+
+impl application::Application {
+    pub fn render_notifications(&mut self, ctx: &egui::Context) {
+        if self.notifications.is_empty() {
+            return;
+        }
+        egui::TopBottomPanel::bottom("notifications_panel").show(ctx, |ui| {
+            let mut to_dismiss = None;
+            for (i, notification) in self.notifications.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.button("Dismiss").clicked() {
+                        to_dismiss = Some(i);
+                    }
+                    if notification.is_error {
+                        ui.colored_label(ui.visuals().error_fg_color, &notification.message);
+                    } else {
+                        ui.label(&notification.message);
+                    }
+                });
+            }
+            if let Some(i) = to_dismiss {
+                self.notifications.remove(i);
+            }
+        });
+    }
+
+    /// Asks whether recalculating may replace the checkpoint that can be resumed.
+    pub fn render_checkpoint_replacement(&mut self, ctx: &egui::Context) {
+        if !self.confirm_checkpoint_replacement {
+            return;
+        }
+        egui::Window::new("Replace the checkpoint?").collapsible(false).resizable(false).show(ctx, |ui| {
+            if let Some(summary) = self.checkpoint_summary {
+                ui.label(format!(
+                    "The new run writes checkpoints, so it replaces the checkpoint of the unfinished run, which has simulated {} out of {}.",
+                    time_unit::format_calendar(summary.time),
+                    time_unit::format_calendar(summary.simulation_time)
+                ));
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Replace").clicked() {
+                    self.recalculate_replacing_checkpoint();
+                }
+                if ui.button("Cancel").clicked() {
+                    self.confirm_checkpoint_replacement = false;
+                }
+            });
+        });
+    }
+}
@@ -0,0 +1,67 @@
+use eframe::egui;
+
+use crate::{application, rendering, settings, simulator};
+
+impl application::Application {
+    /// Renders a map of the planet on which clicking sets the starting position, or adds another one if `start_picker_adds` is set.
+    /// Returns whether the settings were changed.
+    pub fn render_start_picker(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.checkbox(&mut self.start_picker_adds, "Each click adds another Marco")
+            .on_hover_text("Clicking on the map adds a starting position to the list of coordinates instead of moving the starting position.");
+
+        let border = vec![[-180.0, -90.0], [180.0, -90.0], [180.0, 90.0], [-180.0, 90.0], [-180.0, -90.0]];
+        let starts = self
+            .settings
+            .start_positions_deg()
+            .into_iter()
+            .map(|(latitude, longitude)| [longitude, latitude])
+            .collect::<Vec<[f64; 2]>>();
+        let sun_pos_norm = simulator::initial_sun_state::<f64>(&self.settings).0;
+        let subsolar_point = [sun_pos_norm.y.atan2(sun_pos_norm.x).to_degrees(), sun_pos_norm.z.clamp(-1.0, 1.0).asin().to_degrees()];
+
+        let plot = egui_plot::Plot::new("Start picker")
+            .height(180.0)
+            .data_aspect(1.0)
+            .include_x(-180.0)
+            .include_x(180.0)
+            .include_y(-90.0)
+            .include_y(90.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .label_formatter(|_, val| format!("{:.3} deg latitude\n{:.3} deg longitude", val.y, val.x));
+
+        let terrain_polygons = self.settings.terrain_mask.as_deref().map(rendering::terrain::terrain_polygons).unwrap_or_default();
+
+        plot.show(ui, |plot_ui| {
+            for polygon in terrain_polygons {
+                plot_ui.polygon(polygon);
+            }
+            plot_ui.line(egui_plot::Line::new(border).color(egui::Color32::GRAY));
+            plot_ui.points(egui_plot::Points::new(vec![subsolar_point]).color(egui::Color32::GOLD).radius(4.0).name("Subsolar point at the start"));
+            plot_ui.points(egui_plot::Points::new(starts).shape(egui_plot::MarkerShape::Cross).radius(4.0).name("Starting positions"));
+            if !plot_ui.response().clicked() {
+                return;
+            }
+            let Some(point) = plot_ui.pointer_coordinate() else {
+                return;
+            };
+            let (latitude, longitude) = (point.y.clamp(-90.0, 90.0), point.x.clamp(-180.0, 180.0));
+            if self.start_picker_adds {
+                if self.settings.start_positions != settings::StartPositions::List {
+                    self.settings.start_list = self.settings.start_positions_deg();
+                    self.settings.start_positions = settings::StartPositions::List;
+                }
+                self.settings.start_list.push((latitude, longitude));
+            } else {
+                self.settings.start_lat = latitude.to_radians();
+                self.settings.start_lon = longitude.to_radians();
+            }
+            changed = true;
+        });
+        changed
+    }
+}
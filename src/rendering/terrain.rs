@@ -0,0 +1,23 @@
+use eframe::egui;
+
+use crate::terrain;
+
+/// The impassable terrain as polygons for the map views, which show the longitude on the x axis and the latitude on the y axis.
+pub fn terrain_polygons(terrain: &terrain::TerrainMask) -> Vec<egui_plot::Polygon> {
+    terrain
+        .blocked_rectangles(90, 180)
+        .into_iter()
+        .map(|(min_latitude, max_latitude, min_longitude, max_longitude)| {
+            let corners = vec![
+                [min_longitude, min_latitude],
+                [max_longitude, min_latitude],
+                [max_longitude, max_latitude],
+                [min_longitude, max_latitude],
+            ];
+            egui_plot::Polygon::new(corners)
+                .fill_color(egui::Color32::from_rgba_unmultiplied(120, 80, 40, 120))
+                .stroke(egui::Stroke::NONE)
+                .name("Impassable terrain")
+        })
+        .collect()
+}
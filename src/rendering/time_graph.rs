@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+use egui_plot::{self, GridMark};
+
+use crate::{application, data, runs, time_unit};
+
+impl application::Application {
+    /// Renders a graph of some value of the points (given by `value`, in `unit`) against time for the current run and all visible pinned runs.
+    /// The `reference` value of the subsolar point, if given, is shown as a dashed line for the current run.
+    pub fn render_time_graph(&mut self, ui: &mut egui::Ui, id: &str, unit: &'static str, value: fn(&data::Data) -> f64, reference: Option<fn(&data::Data) -> f64>) {
+        self.render_time_unit_selector(ui);
+        let time_scale = self.time_unit.seconds(&self.current_run_settings);
+        let time_symbol = self.time_unit.symbol();
+
+        let time_unit = self.time_unit;
+        let x_fmt = move |x: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| time_unit.format_tick(x.value);
+
+        let y_fmt = move |y: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} {unit}", y.value);
+
+        // The lines of the pinned runs are named after them, so the time of a point can be converted back with the unit of its run
+        let pinned_time_scales = self
+            .pinned_runs
+            .iter()
+            .map(|run| (run.name.clone(), self.time_unit.seconds(&run.settings)))
+            .collect::<HashMap<String, f64>>();
+        let label_fmt = move |s: &str, val: &egui_plot::PlotPoint| {
+            let seconds = val.x * pinned_time_scales.get(s).copied().unwrap_or(time_scale);
+            if s.is_empty() {
+                format!("{:.9} {time_symbol} ({})\n{:.9} {unit}", val.x, time_unit::format_calendar(seconds), val.y)
+            } else {
+                format!("{s}\n{:.9} {time_symbol} ({})\n{:.9} {unit}", val.x, time_unit::format_calendar(seconds), val.y)
+            }
+        };
+
+        let plot = egui_plot::Plot::new(id)
+            // .data_aspect(1.0)
+            .x_axis_formatter(x_fmt)
+            .x_grid_spacer(time_grid_spacer(self.time_unit))
+            .y_axis_formatter(y_fmt)
+            .label_formatter(label_fmt)
+            .legend(egui_plot::Legend::default());
+
+        let mut all_points = Vec::new();
+        let mut all_lines = Vec::new();
+
+        if self.show_current_run {
+            for points_sequence in self.data.values().filter(|points_sequence| !points_sequence.is_empty()) {
+                let colour = points_sequence[0].colour;
+                let points_raw = points_sequence.iter().map(|point| [point.time / time_scale, value(point)]).collect::<Vec<[f64; 2]>>();
+                let points = egui_plot::Points::new(points_raw.clone()).color(colour).highlight(true).name("Current run");
+                let lines = egui_plot::Line::new(egui_plot::PlotPoints::new(points_raw)).color(colour).highlight(true).name("Current run");
+                all_points.push(points);
+                all_lines.push(lines);
+            }
+        }
+        if let (Some(reference), true, true) = (reference, self.show_current_run, self.show_subsolar_point) {
+            // The subsolar point is the same for all the velocities, so any series can be used
+            if let Some(points_sequence) = runs::ordered_series(&self.data).first() {
+                let points_raw = points_sequence.iter().map(|point| [point.time / time_scale, reference(point)]).collect::<Vec<[f64; 2]>>();
+                let lines = egui_plot::Line::new(egui_plot::PlotPoints::new(points_raw))
+                    .color(egui::Color32::GOLD)
+                    .style(egui_plot::LineStyle::dashed_dense())
+                    .name("Subsolar point");
+                all_lines.push(lines);
+            }
+        }
+        for run in self.pinned_runs.iter().filter(|run| run.visible) {
+            // Local solar days depend on the planet, so every run is scaled by its own
+            let run_time_scale = self.time_unit.seconds(&run.settings);
+            if !run_time_scale.is_finite() {
+                continue;
+            }
+            for points_sequence in run.data.values().filter(|points_sequence| !points_sequence.is_empty()) {
+                let colour = points_sequence[0].colour;
+                let points_raw = points_sequence.iter().map(|point| [point.time / run_time_scale, value(point)]).collect::<Vec<[f64; 2]>>();
+                let lines = egui_plot::Line::new(egui_plot::PlotPoints::new(points_raw))
+                    .color(colour)
+                    .style(egui_plot::LineStyle::dashed_loose())
+                    .name(&run.name);
+                all_lines.push(lines);
+            }
+        }
+
+        plot.show(ui, |plot_ui| {
+            for lines in all_lines {
+                plot_ui.line(lines);
+            }
+            for points in all_points {
+                plot_ui.points(points);
+            }
+        });
+    }
+
+    /// Renders a selector of the unit the time axis of the graphs is shown in.
+    pub fn render_time_unit_selector(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Time unit");
+            egui::ComboBox::from_id_source("time_unit").selected_text(self.time_unit.as_ref()).show_ui(ui, |ui| {
+                for unit in time_unit::TimeUnit::ALL {
+                    // The solar day is infinitely long if the planet always faces the star with the same side
+                    let available = unit.seconds(&self.current_run_settings).is_finite();
+                    ui.add_enabled_ui(available, |ui| {
+                        ui.selectable_value(&mut self.time_unit, unit, unit.as_ref());
+                    });
+                }
+            });
+        });
+        if !self.time_unit.seconds(&self.current_run_settings).is_finite() {
+            self.time_unit = time_unit::TimeUnit::Seconds;
+        }
+    }
+}
+
+/// A grid spacer for a time axis in `unit`, putting the grid lines on whole minutes, hours, days or years instead of decimal fractions of the unit.
+pub fn time_grid_spacer(unit: time_unit::TimeUnit) -> impl Fn(egui_plot::GridInput) -> Vec<GridMark> {
+    move |input| {
+        unit.ticks(input.bounds.0, input.bounds.1, input.base_step_size)
+            .into_iter()
+            .map(|(value, step_size)| GridMark { value, step_size })
+            .collect()
+    }
+}
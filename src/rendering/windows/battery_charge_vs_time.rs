@@ -0,0 +1,9 @@
+use eframe::egui;
+
+use crate::application;
+
+impl application::Application {
+    pub fn render_battery_charge_vs_time_graph(&mut self, ui: &mut egui::Ui) {
+        self.render_time_graph(ui, "Battery charge vs time relationship", "%", |point| point.battery_charge * 100.0, None);
+    }
+}
@@ -0,0 +1,74 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+use egui_plot::{self, GridMark};
+
+use crate::{application, summary};
+
+impl application::Application {
+    pub fn render_bifurcation_graph(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Summary");
+            egui::ComboBox::from_id_source("bifurcation_summary")
+                .selected_text(self.bifurcation_summary.as_ref())
+                .show_ui(ui, |ui| {
+                    for summary in summary::Summary::ALL {
+                        ui.selectable_value(&mut self.bifurcation_summary, summary, summary.as_ref());
+                    }
+                });
+            ui.add_enabled_ui(self.bifurcation_summary != summary::Summary::FinalLatitude, |ui| {
+                ui.add(egui::DragValue::new(&mut self.bifurcation_window_days).speed(0.1).clamp_range(0.0..=f64::MAX));
+                ui.label("Last days of the run to summarise");
+            });
+        });
+
+        let unit = self.bifurcation_summary.unit();
+        let x_fmt = |x: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} m/s", x.value);
+
+        let y_fmt = move |y: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} {unit}", y.value);
+
+        let label_fmt = move |s: &str, val: &egui_plot::PlotPoint| {
+            if s.is_empty() {
+                format!("{:.9} m/s\n{:.9} {unit}", val.x, val.y)
+            } else {
+                format!("{s}\n{:.9} m/s\n{:.9} {unit}", val.x, val.y)
+            }
+        };
+
+        let plot = egui_plot::Plot::new("Summary vs velocity relationship")
+            .x_axis_formatter(x_fmt)
+            .y_axis_formatter(y_fmt)
+            .label_formatter(label_fmt)
+            .legend(egui_plot::Legend::default());
+
+        let window = self.bifurcation_window_days * 86400.0;
+        let mut all_points = Vec::new();
+        let mut runs = Vec::new();
+        if self.show_current_run {
+            runs.push(("Current run", &self.data, egui_plot::MarkerShape::Circle));
+        }
+        for run in self.pinned_runs.iter().filter(|run| run.visible) {
+            runs.push((run.name.as_str(), &run.data, egui_plot::MarkerShape::Diamond));
+        }
+        for (name, data, shape) in runs {
+            for points_sequence in data.values() {
+                let Some((velocity, colour)) = points_sequence.first().map(|point| (point.velocity, point.colour)) else {
+                    continue;
+                };
+                let points_raw = self
+                    .bifurcation_summary
+                    .summarise(points_sequence, window)
+                    .into_iter()
+                    .map(|value| [velocity, value])
+                    .collect::<Vec<[f64; 2]>>();
+                all_points.push(egui_plot::Points::new(points_raw).color(colour).shape(shape).radius(2.0).name(name));
+            }
+        }
+
+        plot.show(ui, |plot_ui| {
+            for points in all_points {
+                plot_ui.points(points);
+            }
+        });
+    }
+}
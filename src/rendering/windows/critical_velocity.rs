@@ -0,0 +1,82 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+use egui_plot::{self, GridMark};
+
+use crate::{application, critical_velocity, message_passers};
+
+impl application::Application {
+    pub fn render_critical_velocity_graph(&mut self, ui: &mut egui::Ui) {
+        let search = &mut self.critical_velocity_search;
+        ui.horizontal(|ui| {
+            ui.label("Criterion");
+            egui::ComboBox::from_id_source("critical_velocity_criterion")
+                .selected_text(search.criterion.as_ref())
+                .show_ui(ui, |ui| {
+                    for criterion in critical_velocity::Criterion::ALL {
+                        ui.selectable_value(&mut search.criterion, criterion, criterion.as_ref());
+                    }
+                });
+            ui.add(egui::DragValue::new(&mut search.min_latitude).speed(0.1).clamp_range(-90.0..=search.max_latitude).suffix(" deg"));
+            ui.label("to");
+            ui.add(egui::DragValue::new(&mut search.max_latitude).speed(0.1).clamp_range(search.min_latitude..=90.0).suffix(" deg"));
+            ui.label("Starting latitudes");
+            ui.add(egui::DragValue::new(&mut search.latitudes_count).speed(1).clamp_range(1..=1000));
+            ui.label("Latitudes count");
+        });
+        ui.horizontal(|ui| {
+            let search = &mut self.critical_velocity_search;
+            ui.add(egui::DragValue::new(&mut search.max_velocity).speed(0.1).clamp_range(0.0..=f64::MAX).suffix(" m/s"));
+            ui.label("Maximum velocity");
+            ui.add(egui::DragValue::new(&mut search.tolerance).speed(0.001).clamp_range(0.0001..=f64::MAX).suffix(" m/s"));
+            ui.label("Tolerance");
+            let idle = self.calculation_stage == message_passers::CalculationStage::End;
+            if ui
+                .add_enabled(idle, egui::Button::new("Calculate"))
+                .on_hover_text("Runs a bisection search for every starting latitude, using the current settings for everything else")
+                .clicked()
+            {
+                self.search_critical_velocities();
+            }
+            if ui.add_enabled(!self.critical_velocities.is_empty(), egui::Button::new("Export the curve")).clicked() {
+                self.export_critical_velocities();
+            }
+        });
+
+        let x_fmt = |x: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} deg", x.value);
+
+        let y_fmt = |y: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} m/s", y.value);
+
+        let label_fmt = |s: &str, val: &egui_plot::PlotPoint| {
+            if s.is_empty() {
+                format!("{:.9} deg\n{:.9} m/s", val.x, val.y)
+            } else {
+                format!("{s}\n{:.9} deg\n{:.9} m/s", val.x, val.y)
+            }
+        };
+
+        let plot = egui_plot::Plot::new("Critical velocity vs starting latitude relationship")
+            .x_axis_formatter(x_fmt)
+            .y_axis_formatter(y_fmt)
+            .label_formatter(label_fmt)
+            .legend(egui_plot::Legend::default());
+
+        let found = self
+            .critical_velocities
+            .iter()
+            .filter_map(|(latitude, velocity)| velocity.map(|velocity| [*latitude, velocity]))
+            .collect::<Vec<[f64; 2]>>();
+        let not_found = self
+            .critical_velocities
+            .iter()
+            .filter(|(_, velocity)| velocity.is_none())
+            .map(|(latitude, _)| [*latitude, self.critical_velocity_search.max_velocity])
+            .collect::<Vec<[f64; 2]>>();
+
+        plot.show(ui, |plot_ui| {
+            plot_ui.line(egui_plot::Line::new(found.clone()).name("Critical velocity"));
+            plot_ui.points(egui_plot::Points::new(found).radius(2.0).name("Critical velocity"));
+            plot_ui.points(egui_plot::Points::new(not_found).shape(egui_plot::MarkerShape::Up).radius(4.0).name("Faster than the maximum velocity"));
+        });
+    }
+}
@@ -0,0 +1,40 @@
+use eframe::egui;
+
+use crate::{application, time_unit};
+
+impl application::Application {
+    pub fn render_eclipse_list(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if !self.current_run_settings.moon {
+                ui.label("The current run has no moon, enable it in the planet orbit parameters to see eclipses.");
+            } else {
+                ui.label(format!("{} eclipses of the star by the moon", self.eclipses.len()));
+            }
+            if ui.add_enabled(!self.eclipses.is_empty(), egui::Button::new("Export the eclipses")).clicked() {
+                self.export_eclipses();
+            }
+        });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            egui::Grid::new("eclipses").striped(true).show(ui, |ui| {
+                ui.label("Velocity");
+                ui.label("Start");
+                ui.label("End");
+                ui.label("Duration");
+                ui.label("Largest part of the star covered");
+                ui.end_row();
+                for eclipse in &self.eclipses {
+                    match self.data.get(&eclipse.series).and_then(|points| points.first()) {
+                        Some(point) => ui.label(format!("{:.3} m/s", point.velocity)),
+                        None => ui.label(format!("Series {}", eclipse.series)),
+                    };
+                    ui.label(time_unit::format_calendar(eclipse.start));
+                    ui.label(time_unit::format_calendar(eclipse.end));
+                    ui.label(time_unit::format_calendar(eclipse.end - eclipse.start));
+                    ui.label(format!("{:.1}%", eclipse.max_coverage * 100.0));
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
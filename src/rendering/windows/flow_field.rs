@@ -0,0 +1,58 @@
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+use egui_plot::{self, GridMark};
+
+use crate::{application, rendering, runs};
+
+impl application::Application {
+    pub fn render_flow_field(&mut self, ui: &mut egui::Ui) {
+        ui.label("Every arrow goes from the starting position of a series of the current run to where it ends up, the shorter way around the planet.");
+
+        let x_fmt = |x: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} deg", x.value);
+
+        let y_fmt = |y: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} deg", y.value);
+
+        let label_fmt = |s: &str, val: &egui_plot::PlotPoint| {
+            if s.is_empty() {
+                format!("{:.9} deg latitude\n{:.9} deg longitude", val.y, val.x)
+            } else {
+                format!("{s}\n{:.9} deg latitude\n{:.9} deg longitude", val.y, val.x)
+            }
+        };
+
+        let plot = egui_plot::Plot::new("Flow field of the starting positions")
+            .data_aspect(1.0)
+            .x_axis_formatter(x_fmt)
+            .y_axis_formatter(y_fmt)
+            .label_formatter(label_fmt)
+            .legend(egui_plot::Legend::default());
+
+        let mut all_arrows = Vec::new();
+        let mut all_points = Vec::new();
+        for points_sequence in runs::ordered_series(&self.data) {
+            let (Some(start), Some(end)) = (points_sequence.first(), points_sequence.last()) else {
+                continue;
+            };
+            let origin = [start.longitude, start.latitude];
+            let tip = [start.longitude + runs::normalise_longitude(end.longitude - start.longitude), end.latitude];
+            let name = format!("{:.3} m/s", start.velocity);
+            all_arrows.push(egui_plot::Arrows::new(vec![origin], vec![tip]).color(start.colour).name(&name));
+            all_points.push(egui_plot::Points::new(vec![origin]).color(start.colour).radius(2.0).name(&name));
+        }
+
+        let terrain_polygons = self.settings.terrain_mask.as_deref().map(rendering::terrain::terrain_polygons).unwrap_or_default();
+
+        plot.show(ui, |plot_ui| {
+            for polygon in terrain_polygons {
+                plot_ui.polygon(polygon);
+            }
+            for arrows in all_arrows {
+                plot_ui.arrows(arrows);
+            }
+            for points in all_points {
+                plot_ui.points(points);
+            }
+        });
+    }
+}
@@ -0,0 +1,58 @@
+use eframe::egui;
+
+use crate::{application, settings};
+
+impl application::Application {
+    pub fn render_history(&mut self, ctx: &egui::Context) {
+        let mut opened = self.windows_opened.history;
+        let mut to_restore = None;
+        egui::Window::new("History").open(&mut opened).show(ctx, |ui| {
+            if self.run_log.is_empty() {
+                ui.label("Nothing has been calculated yet.");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (i, entry) in self.run_log.iter().enumerate().rev() {
+                    let duration = match entry.duration {
+                        Some(duration) => format!("took {:.3} s", duration.as_secs_f64()),
+                        None => String::from("running"),
+                    };
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Restore")
+                            .on_hover_text("Restores the settings this run was calculated with. This can be undone in the settings window.")
+                            .clicked()
+                        {
+                            to_restore = Some(entry.settings.clone());
+                        }
+                        ui.label(format!("Run {} ({duration})", i + 1));
+                    });
+                    let settings = &entry.settings;
+                    let start = match settings.start_positions {
+                        settings::StartPositions::Single => format!("start at {:.3} deg, {:.3} deg", settings.start_lat.to_degrees(), settings.start_lon.to_degrees()),
+                        start_positions => format!("{} starting positions ({})", settings.start_positions_deg().len(), start_positions.as_ref().to_lowercase()),
+                    };
+                    ui.label(format!(
+                        "{} velocities from {:.3} m/s to {:.3} m/s, {start}, axis tilt {:.3} deg, {} s simulated with a {} s timestep",
+                        settings.velocities_count,
+                        settings.marco_min_velocity,
+                        settings.marco_max_velocity,
+                        settings.rotational_axis_tilt.to_degrees(),
+                        settings.simulation_time,
+                        settings.timestep
+                    ));
+                    if !entry.headline.is_empty() {
+                        ui.label(&entry.headline);
+                    }
+                    egui::CollapsingHeader::new("All settings").id_source(("run log settings", i)).show(ui, |ui| {
+                        ui.monospace(format!("{settings:#?}"));
+                    });
+                    ui.separator();
+                }
+            });
+        });
+        if let Some(settings) = to_restore {
+            self.settings_history.replace(&mut self.settings, settings);
+        }
+        self.windows_opened.history = opened;
+    }
+}
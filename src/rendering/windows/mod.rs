@@ -1,3 +1,14 @@
+pub mod battery_charge_vs_time;
+pub mod bifurcation;
+pub mod critical_velocity;
+pub mod eclipses;
+pub mod flow_field;
+pub mod history;
 pub mod latitude_vs_time;
 pub mod longitude_vs_time;
+pub mod run_difference;
+pub mod runs;
 pub mod settings;
+pub mod speed_vs_time;
+pub mod subsolar_bearing_vs_time;
+pub mod subsolar_distance_vs_time;
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use eframe::egui;
+use egui_plot::{self, GridMark};
+
+use crate::{application, data, rendering, runs};
+
+impl application::Application {
+    pub fn render_run_difference_graph(&mut self, ui: &mut egui::Ui) {
+        if self.pinned_runs.is_empty() {
+            ui.label("Pin at least one run to compare runs with each other.");
+            return;
+        }
+        let mut comparison = self.run_comparison;
+        ui.horizontal(|ui| {
+            self.render_run_series_selector(ui, "First run", &mut comparison.first, &mut comparison.first_series);
+            ui.separator();
+            self.render_run_series_selector(ui, "Second run", &mut comparison.second, &mut comparison.second_series);
+        });
+        self.run_comparison = comparison;
+
+        let (Some(first), Some(second)) = (self.run_data(comparison.first), self.run_data(comparison.second)) else {
+            ui.label("One of the selected runs no longer exists.");
+            return;
+        };
+        let (Some(first), Some(second)) = (
+            runs::ordered_series(first).get(comparison.first_series).copied(),
+            runs::ordered_series(second).get(comparison.second_series).copied(),
+        ) else {
+            ui.label("One of the selected runs has no points.");
+            return;
+        };
+        let differences = runs::difference(first, second);
+
+        self.render_time_unit_selector(ui);
+        // Each run is scaled with its own settings, so a unit of different lengths would put the same time at different places
+        let (Some(time_scale), Some(second_time_scale)) = (self.run_time_scale(comparison.first), self.run_time_scale(comparison.second)) else {
+            return;
+        };
+        if !time_scale.is_finite() {
+            ui.label("The star never returns to the same place in the sky of the first run, choose another time unit to compare the runs.");
+            return;
+        }
+        if time_scale != second_time_scale {
+            ui.label(format!(
+                "The runs have {} of different lengths, choose another time unit to compare them.",
+                self.time_unit.as_ref().to_lowercase()
+            ));
+            return;
+        }
+        let time_symbol = self.time_unit.symbol();
+
+        let time_unit = self.time_unit;
+        let x_fmt = move |x: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| time_unit.format_tick(x.value);
+
+        let y_fmt = |y: GridMark, _max_len: usize, _range: &RangeInclusive<f64>| format!("{:.3} deg", y.value);
+
+        let label_fmt = move |s: &str, val: &egui_plot::PlotPoint| format!("{s}\n{:.9} {time_symbol}\n{:.9} deg", val.x, val.y);
+
+        let plot = egui_plot::Plot::new("Run difference vs time relationship")
+            .x_axis_formatter(x_fmt)
+            .x_grid_spacer(rendering::time_graph::time_grid_spacer(self.time_unit))
+            .y_axis_formatter(y_fmt)
+            .label_formatter(label_fmt)
+            .legend(egui_plot::Legend::default());
+
+        let latitude_difference = differences.iter().map(|difference| [difference[0] / time_scale, difference[1]]).collect::<Vec<[f64; 2]>>();
+        let longitude_difference = differences.iter().map(|difference| [difference[0] / time_scale, difference[2]]).collect::<Vec<[f64; 2]>>();
+
+        plot.show(ui, |plot_ui| {
+            plot_ui.line(egui_plot::Line::new(egui_plot::PlotPoints::new(latitude_difference)).highlight(true).name("Latitude difference"));
+            plot_ui.line(egui_plot::Line::new(egui_plot::PlotPoints::new(longitude_difference)).highlight(true).name("Longitude difference"));
+        });
+    }
+
+    fn render_run_series_selector(&self, ui: &mut egui::Ui, label: &str, selection: &mut runs::RunSelection, series: &mut usize) {
+        ui.label(label);
+        egui::ComboBox::from_id_source(format!("{label} run")).selected_text(self.run_name(*selection)).show_ui(ui, |ui| {
+            ui.selectable_value(selection, runs::RunSelection::Current, self.run_name(runs::RunSelection::Current));
+            for i in 0..self.pinned_runs.len() {
+                ui.selectable_value(selection, runs::RunSelection::Pinned(i), self.run_name(runs::RunSelection::Pinned(i)));
+            }
+        });
+        // Several series share a velocity when there are more starting positions, so the start tells them apart
+        let labels = self
+            .run_data(*selection)
+            .map(|data| {
+                runs::ordered_series(data)
+                    .iter()
+                    .map(|series| format!("{:.3} m/s from {:.2}°, {:.2}°", series[0].velocity, series[0].latitude, series[0].longitude))
+                    .collect::<Vec<String>>()
+            })
+            .unwrap_or_default();
+        egui::ComboBox::from_id_source(format!("{label} series"))
+            .selected_text(labels.get(*series).cloned().unwrap_or_default())
+            .show_ui(ui, |ui| {
+                for (i, series_label) in labels.iter().enumerate() {
+                    ui.selectable_value(series, i, series_label);
+                }
+            });
+    }
+
+    fn run_data(&self, selection: runs::RunSelection) -> Option<&HashMap<usize, Vec<data::Data>>> {
+        match selection {
+            runs::RunSelection::Current => Some(&self.data),
+            runs::RunSelection::Pinned(i) => self.pinned_runs.get(i).map(|run| &run.data),
+        }
+    }
+
+    /// The length (s) of the selected time unit in the selected run, with that run's own settings.
+    fn run_time_scale(&self, selection: runs::RunSelection) -> Option<f64> {
+        let settings = match selection {
+            runs::RunSelection::Current => &self.current_run_settings,
+            runs::RunSelection::Pinned(i) => &self.pinned_runs.get(i)?.settings,
+        };
+        Some(self.time_unit.seconds(settings))
+    }
+
+    fn run_name(&self, selection: runs::RunSelection) -> String {
+        match selection {
+            runs::RunSelection::Current => String::from("Current run"),
+            runs::RunSelection::Pinned(i) => self.pinned_runs.get(i).map(|run| run.name.clone()).unwrap_or_default(),
+        }
+    }
+}
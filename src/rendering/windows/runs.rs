@@ -0,0 +1,50 @@
+use eframe::egui;
+
+use crate::application;
+
+impl application::Application {
+    pub fn render_runs(&mut self, ctx: &egui::Context) {
+        let mut opened = self.windows_opened.runs;
+        egui::Window::new("Runs").open(&mut opened).show(ctx, |ui| {
+            ui.checkbox(&mut self.show_current_run, "Show the current run");
+            ui.checkbox(&mut self.show_subsolar_point, "Show the subsolar point of the current run")
+                .on_hover_text("The point on the planet where the star is in the zenith, shown in the latitude and longitude graphs.");
+            ui.add_enabled_ui(self.calculation_stage == crate::message_passers::CalculationStage::End && !self.data.is_empty(), |ui| {
+                if ui
+                    .button("Pin the current run")
+                    .on_hover_text("Keeps a copy of the current run so it is not lost when recalculating and can be compared with other runs.")
+                    .clicked()
+                {
+                    self.pin_current_run();
+                }
+                if ui
+                    .button("Export the current run")
+                    .on_hover_text("Writes all points of the current run, including the distance and bearing to the subsolar point, into a CSV file.")
+                    .clicked()
+                {
+                    self.export_current_run();
+                }
+            });
+            ui.separator();
+            ui.heading("Pinned runs");
+            if self.pinned_runs.is_empty() {
+                ui.label("No runs are pinned yet.");
+            }
+            let mut to_remove = None;
+            for (i, run) in self.pinned_runs.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut run.visible, "").on_hover_text("Show this run in the graphs");
+                    ui.text_edit_singleline(&mut run.name);
+                    if ui.button("Remove").clicked() {
+                        to_remove = Some(i);
+                    }
+                });
+            }
+            if let Some(i) = to_remove {
+                self.pinned_runs.remove(i);
+                self.run_comparison.remove_pinned(i);
+            }
+        });
+        self.windows_opened.runs = opened;
+    }
+}
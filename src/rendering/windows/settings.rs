@@ -1,14 +1,27 @@
-use std::f32::consts::PI;
+use std::f64::consts::PI;
 
 use eframe::egui;
 
-use crate::application;
+use crate::{application, checkpoint, eclipse, message_passers, precision, settings, solar_position, terrain};
 
 impl application::Application {
     pub fn render_settings(&mut self, ctx: &egui::Context) {
         let mut opened = self.windows_opened.settings;
+        let settings_before = self.settings.clone();
+        let mut navigated_history = false;
         egui::Window::new("Settings").open(&mut opened).show(ctx, |ui| {
 			let mut anything_changed = false;
+			ui.horizontal(|ui| {
+				if ui.add_enabled(self.settings_history.can_undo(), egui::Button::new("Undo")).clicked() {
+					navigated_history = self.settings_history.undo(&mut self.settings);
+					anything_changed |= navigated_history;
+				}
+				if ui.add_enabled(self.settings_history.can_redo(), egui::Button::new("Redo")).clicked() {
+					navigated_history = self.settings_history.redo(&mut self.settings);
+					anything_changed |= navigated_history;
+				}
+			});
+			ui.separator();
             ui.checkbox(&mut self.settings.recalculate_on_change, "Recalculate on change").on_hover_text("If this option is enabled the simulation will be recalculated every time any of the parameters changes. Can be great for playing with starting values, but can be computationally expensive and therefore make the application run quite slow.");
 			ui.separator();
 			ui.heading("Marco parameters");
@@ -26,31 +39,234 @@ impl application::Application {
 				ui.label("Marco's velocities to simulate (count)");
 			});
 			ui.horizontal(|ui| {
-				anything_changed |= ui.drag_angle(&mut self.settings.start_lat).changed();
+				anything_changed |= drag_angle(ui, &mut self.settings.start_lat).changed();
 				self.settings.start_lat = self.settings.start_lat.clamp(-PI / 2.0, PI / 2.0);
 				ui.label("Starting latitude (deg)");
 			});
 			ui.horizontal(|ui| {
-				anything_changed |= ui.drag_angle(&mut self.settings.start_lon).changed();
-				self.settings.start_lon = self.settings.start_lon.clamp(-PI / 2.0, PI / 2.0);
+				anything_changed |= drag_angle(ui, &mut self.settings.start_lon).changed();
+				self.settings.start_lon = self.settings.start_lon.clamp(-PI, PI);
 				ui.label("Starting longitude (deg)");
 			});
+			ui.horizontal(|ui| {
+				egui::ComboBox::from_id_source("start_positions").selected_text(self.settings.start_positions.as_ref()).show_ui(ui, |ui| {
+					for start_positions in settings::StartPositions::ALL {
+						anything_changed |= ui.selectable_value(&mut self.settings.start_positions, start_positions, start_positions.as_ref()).changed();
+					}
+				});
+				ui.label("Starting positions").on_hover_text("Every starting position is simulated with every velocity, which can be used to map where Marcos starting all over the planet drift to.");
+			});
+			match self.settings.start_positions {
+				settings::StartPositions::Single => {}
+				settings::StartPositions::Grid | settings::StartPositions::Meridian => {
+					let grid = &mut self.settings.start_grid;
+					ui.horizontal(|ui| {
+						anything_changed |= ui.add(egui::DragValue::new(&mut grid.min_latitude).speed(0.1).clamp_range(-90.0..=grid.max_latitude)).changed();
+						ui.label("to");
+						anything_changed |= ui.add(egui::DragValue::new(&mut grid.max_latitude).speed(0.1).clamp_range(grid.min_latitude..=90.0)).changed();
+						ui.label("in");
+						anything_changed |= ui.add(egui::DragValue::new(&mut grid.latitudes_count).speed(0.1).clamp_range(1..=1000)).changed();
+						ui.label("Starting latitudes (deg, count)");
+					});
+					if self.settings.start_positions == settings::StartPositions::Grid {
+						ui.horizontal(|ui| {
+							anything_changed |= ui.add(egui::DragValue::new(&mut grid.min_longitude).speed(0.1).clamp_range(-180.0..=grid.max_longitude)).changed();
+							ui.label("to");
+							anything_changed |= ui.add(egui::DragValue::new(&mut grid.max_longitude).speed(0.1).clamp_range(grid.min_longitude..=180.0)).changed();
+							ui.label("in");
+							anything_changed |= ui.add(egui::DragValue::new(&mut grid.longitudes_count).speed(0.1).clamp_range(1..=1000)).changed();
+							ui.label("Starting longitudes (deg, count)");
+						});
+					} else {
+						ui.label("The meridian is given by the starting longitude.");
+					}
+				}
+				settings::StartPositions::List => {
+					ui.label("Starting positions, one \"latitude, longitude\" pair (deg) per line");
+					let response = ui.add(egui::TextEdit::multiline(&mut self.start_list_text).hint_text("30, -90"));
+					let parsed = settings::parse_coordinates(&self.start_list_text);
+					if response.has_focus() {
+						match parsed {
+							Ok(coordinates) if coordinates != self.settings.start_list => {
+								self.settings.start_list = coordinates;
+								anything_changed = true;
+							}
+							Ok(_) => {}
+							Err(err) => {
+								ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+							}
+						}
+					} else if parsed.ok().as_ref() != Some(&self.settings.start_list) {
+						// The list was changed by undo or redo, or the text is not valid and the last valid list is shown again
+						self.start_list_text = settings::format_coordinates(&self.settings.start_list);
+					}
+				}
+			}
+			egui::CollapsingHeader::new("Pick on a map").show(ui, |ui| {
+				anything_changed |= self.render_start_picker(ui);
+			});
+			anything_changed |= ui.checkbox(&mut self.settings.rover_energy_model, "Solar-powered rover").on_hover_text("Marco's speed follows from the energy his solar panels and battery can provide, the velocity being only his top speed.").changed();
+			ui.add_enabled_ui(self.settings.rover_energy_model, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rover_solar_power).clamp_range(0.0..=f64::MAX)).changed();
+					ui.label("Solar panel power (W)").on_hover_text("The power with the star in the zenith, it is proportional to the sine of the elevation of the star.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rover_battery_capacity).clamp_range(0.001..=f64::MAX)).changed();
+					ui.label("Battery capacity (Wh)");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rover_energy_per_metre).clamp_range(0.001..=f64::MAX)).changed();
+					ui.label("Energy needed per metre (J/m)");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rover_min_charge).speed(0.01).clamp_range(0.0..=1.0)).changed();
+					ui.label("Minimum charge to move (fraction)").on_hover_text("A rover which ran out of energy waits until its battery is charged to this fraction of its capacity.");
+				});
+			});
+			anything_changed |= ui.checkbox(&mut self.settings.walking_window, "Walk only at some hours").on_hover_text("Marco only walks between these hours of local solar time, 12 h being when the star crosses his meridian. The window can go over midnight.").changed();
+			ui.add_enabled_ui(self.settings.walking_window, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.walking_window_start).speed(0.1).clamp_range(0.0..=24.0)).changed();
+					ui.label("to");
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.walking_window_end).speed(0.1).clamp_range(0.0..=24.0)).changed();
+					ui.label("Walking hours (h of local solar time)");
+				});
+			});
+			ui.horizontal(|ui| {
+				anything_changed |= ui.checkbox(&mut self.settings.limit_walking_hours, "").changed();
+				ui.add_enabled_ui(self.settings.limit_walking_hours, |ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.max_walking_hours).speed(0.1).clamp_range(0.0..=f64::MAX)).changed();
+					ui.label("Maximum walking time per local solar day (h)");
+				});
+			});
+			ui.horizontal(|ui| {
+				anything_changed |= ui.checkbox(&mut self.settings.zenith_rest, "").changed();
+				ui.add_enabled_ui(self.settings.zenith_rest, |ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.zenith_rest_elevation).speed(0.1).clamp_range(0.0..=90.0)).changed();
+					ui.label("Rest while the star is higher than (deg)").on_hover_text("Marco takes a break around noon, while the star is this high or higher above his horizon.");
+				});
+			});
 			ui.separator();
 			ui.heading("Planet parameters");
+			ui.horizontal(|ui| {
+				if ui.button("Earth").on_hover_text("Sets the radius, rotation and orbit of the planet to those of the Earth.").clicked() {
+					self.settings.apply_earth_preset();
+					anything_changed = true;
+				}
+				if ui.button("Saturn").on_hover_text("Sets the radius, rotation, orbit and rings of the planet to those of Saturn.").clicked() {
+					self.settings.apply_saturn_preset();
+					anything_changed = true;
+				}
+			});
 			ui.horizontal(|ui| {
 				anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.planet_radius)).changed();
 				self.settings.planet_radius = self.settings.planet_radius.max(10.0_f64.powi(-6));
 				ui.label("Planet radius (km)");
 			});
-			ui.horizontal(|ui| {
-				anything_changed |= ui.drag_angle(&mut self.settings.rotational_axis_tilt).changed();
-				self.settings.rotational_axis_tilt = self.settings.rotational_axis_tilt.clamp(-PI, PI);
-				ui.label("Rotational axis tilt (deg)").on_hover_text("The tilt of the rotational axis of the planet, measured from the normal to the ecliptic. Positive values make it point towards the star at the start, negative away.");
+			let response = ui
+				.checkbox(&mut self.settings.use_start_date, "Start at a date on the Earth")
+				.on_hover_text("Starts the star where the Sun is seen from the Earth at the date and time (UTC), which also gives the tilt of the rotational axis. The planet and its orbit are set to those of the Earth, as the Sun would drift away from the real one with other periods.");
+			if response.changed() {
+				if self.settings.use_start_date {
+					self.settings.apply_earth_preset();
+				}
+				anything_changed = true;
+			}
+			ui.add_enabled_ui(self.settings.use_start_date, |ui| {
+				ui.horizontal(|ui| {
+					let response = ui.add(egui::TextEdit::singleline(&mut self.start_date_text).hint_text("2024-06-20 20:51:00"));
+					let parsed = self.start_date_text.parse::<solar_position::UtcDateTime>();
+					if response.has_focus() {
+						match parsed {
+							Ok(date) if date != self.settings.start_date => {
+								self.settings.start_date = date;
+								anything_changed = true;
+							}
+							Ok(_) => {}
+							Err(err) => {
+								ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+							}
+						}
+					} else if parsed.ok() != Some(self.settings.start_date) {
+						// The date was changed by undo or redo, or the text is not valid and the last valid date is shown again
+						self.start_date_text = self.settings.start_date.to_string();
+					}
+					ui.label("Start date and time (UTC)");
+				});
+			});
+			ui.add_enabled_ui(!self.settings.use_start_date, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= drag_angle(ui, &mut self.settings.rotational_axis_tilt).changed();
+					self.settings.rotational_axis_tilt = self.settings.rotational_axis_tilt.clamp(-PI, PI);
+					ui.label("Rotational axis tilt (deg)").on_hover_text("The tilt of the rotational axis of the planet, measured from the normal to the ecliptic. Positive values make it point towards the star at the start, negative away.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.orbital_phase).speed(1.0).clamp_range(0.0..=360.0).suffix("°")).changed();
+					ui.label("Orbital phase at the start (deg)").on_hover_text("Where in its orbit the planet starts: 0° is the northern summer solstice, 90° the northern autumn equinox, 180° the northern winter solstice and 270° the northern spring equinox.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.checkbox(&mut self.settings.set_start_local_solar_time, "Local solar time at the start (h)").changed();
+					ui.add_enabled_ui(self.settings.set_start_local_solar_time, |ui| {
+						anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.start_local_solar_time).speed(0.1).clamp_range(0.0..=23.99)).changed();
+					})
+					.response
+					.on_hover_text("The local solar time on the meridian of the starting longitude, 12 h being when the star is the highest. Otherwise the star starts above the prime meridian.");
+				});
+			});
+			// A start at a date needs the periods of the Earth
+			ui.add_enabled_ui(!self.settings.use_start_date, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rotational_period)).changed();
+					self.settings.rotational_period = self.settings.rotational_period.max(10.0_f64.powi(-6));
+					ui.label("Sideric rotation period (h)");
+				});
+			});
+			anything_changed |= ui
+				.checkbox(&mut self.settings.rings, "Rings")
+				.on_hover_text("Rings in the plane of the equator. Marco does not walk in their shadow, which falls on the winter hemisphere.")
+				.changed();
+			ui.add_enabled_ui(self.settings.rings, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.ring_inner_radius).speed(10.0).clamp_range(self.settings.planet_radius..=self.settings.ring_outer_radius)).changed();
+					ui.label("to");
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.ring_outer_radius).speed(10.0).clamp_range(self.settings.ring_inner_radius..=f64::MAX)).changed();
+					ui.label("Ring radii (km)");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.ring_opacity).speed(0.01).clamp_range(0.0..=1.0)).changed();
+					ui.label("Ring opacity").on_hover_text("The fraction of the starlight the rings block, which lowers the power of a solar-powered rover in their shadow.");
+				});
 			});
 			ui.horizontal(|ui| {
-				anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.rotational_period)).changed();
-				self.settings.rotational_period = self.settings.rotational_period.max(10.0_f64.powi(-6));
-				ui.label("Sideric rotation period (h)");
+				ui.add(egui::TextEdit::singleline(&mut self.terrain_mask_path).hint_text("mask.png"));
+				if ui.button("Load").on_hover_text("Loads an equirectangular PNG image of the whole planet, dark pixels are impassable and transparent or light ones passable.").clicked() {
+					match terrain::TerrainMask::load(std::path::Path::new(&self.terrain_mask_path)) {
+						Ok(mask) => {
+							self.settings.terrain_mask = Some(std::sync::Arc::new(mask));
+							anything_changed = true;
+						}
+						Err(err) => self.notifications.push(application::Notification::error(err.to_string())),
+					}
+				}
+				if ui.add_enabled(self.settings.terrain_mask.is_some(), egui::Button::new("Remove")).clicked() {
+					self.settings.terrain_mask = None;
+					anything_changed = true;
+				}
+				ui.label("Terrain mask");
+			});
+			if let Some(mask) = &self.settings.terrain_mask {
+				ui.label(format!("Impassable terrain from {}", mask.path.display()));
+			}
+			ui.add_enabled_ui(self.settings.terrain_mask.is_some(), |ui| {
+				ui.horizontal(|ui| {
+					egui::ComboBox::from_id_source("blocked_behaviour").selected_text(self.settings.blocked_behaviour.as_ref()).show_ui(ui, |ui| {
+						for behaviour in terrain::BlockedBehaviour::ALL {
+							anything_changed |= ui.selectable_value(&mut self.settings.blocked_behaviour, behaviour, behaviour.as_ref()).changed();
+						}
+					});
+					ui.label("When the path is blocked");
+				});
 			});
 			ui.separator();
 			ui.heading("Planet orbit parameters");
@@ -59,9 +275,50 @@ impl application::Application {
 				self.settings.sun_distance = self.settings.sun_distance.max(10.0_f64.powi(-6));
 				ui.label("Semi-major axis (km)");
 			});
+			// A start at a date needs the periods of the Earth
+			ui.add_enabled_ui(!self.settings.use_start_date, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.orbital_period).speed(0.01)).changed();
+					ui.label("Orbital period (years)");
+				});
+			});
 			ui.horizontal(|ui| {
-				anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.orbital_period).speed(0.01)).changed();
-				ui.label("Orbital period (years)");
+				anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.star_radius).speed(100.0).clamp_range(0.001..=f64::MAX)).changed();
+				ui.label("Star radius (km)").on_hover_text("Gives the size of the disc of the star, which matters for how much of it the moon covers.");
+			});
+			anything_changed |= ui
+				.checkbox(&mut self.settings.moon, "Moon")
+				.on_hover_text("A moon on a circular orbit around the planet, which eclipses the star when it passes in front of it. The eclipses are listed in the list of eclipses.")
+				.changed();
+			ui.add_enabled_ui(self.settings.moon, |ui| {
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.moon_orbit_radius).speed(100.0).clamp_range(0.001..=f64::MAX)).changed();
+					ui.label("Moon orbit radius (km)");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.moon_orbital_period).speed(0.01)).changed();
+					ui.label("Moon orbital period (days)").on_hover_text("The sidereal period, negative for a retrograde orbit.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.moon_inclination).speed(0.1).clamp_range(-180.0..=180.0).suffix("°")).changed();
+					ui.label("Moon orbit inclination (deg)").on_hover_text("The tilt of the orbit of the moon to the ecliptic. The orbit crosses the ecliptic in the direction of the star at the start.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.moon_phase).speed(1.0).clamp_range(0.0..=360.0).suffix("°")).changed();
+					ui.label("Moon phase at the start (deg)").on_hover_text("How far the moon is ahead of the star along its orbit: 0° is a new moon, which can eclipse the star, and 180° a full moon.");
+				});
+				ui.horizontal(|ui| {
+					anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.moon_radius).speed(10.0).clamp_range(0.001..=f64::MAX)).changed();
+					ui.label("Moon radius (km)");
+				});
+				ui.horizontal(|ui| {
+					egui::ComboBox::from_id_source("eclipse_behaviour").selected_text(self.settings.eclipse_behaviour.as_ref()).show_ui(ui, |ui| {
+						for behaviour in eclipse::EclipseBehaviour::ALL {
+							anything_changed |= ui.selectable_value(&mut self.settings.eclipse_behaviour, behaviour, behaviour.as_ref()).changed();
+						}
+					});
+					ui.label("During an eclipse").on_hover_text("Marco either stops while any part of the star is covered or walks slower the more of it is covered. A solar-powered rover always gets less power.");
+				});
 			});
 			ui.separator();
 			ui.heading("Simulation parameters");
@@ -77,6 +334,29 @@ impl application::Application {
 				anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.points_to_show)).changed();
 				ui.label("Number of points to show in the graph per simulated velocity (approximate value, usually ± 1)");
 			});
+			ui.horizontal(|ui| {
+				egui::ComboBox::from_id_source("precision").selected_text(self.settings.precision.as_ref()).show_ui(ui, |ui| {
+					for precision in precision::Precision::ALL {
+						anything_changed |= ui.selectable_value(&mut self.settings.precision, precision, precision.as_ref()).changed();
+					}
+				});
+				ui.label("Precision").on_hover_text("Comparing a run with a run in a different precision shows how much round-off accumulates over the steps. Double-double precision is many times slower.");
+			});
+			if self.settings.precision != precision::Precision::Double {
+				self.settings.checkpoint = false;
+			}
+			ui.add_enabled_ui(self.settings.precision == precision::Precision::Double, |ui| {
+				anything_changed |= ui
+					.checkbox(&mut self.settings.checkpoint, "Write checkpoints")
+					.on_hover_text(format!("Periodically saves the running simulation into {}/{}, so a long run can be resumed after the application is closed", marco_on_a_desert_planet::OUTPUT_DIRECTORY, checkpoint::CHECKPOINT_FILE_NAME))
+					.changed();
+				ui.add_enabled_ui(self.settings.checkpoint, |ui| {
+					ui.horizontal(|ui| {
+						anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.checkpoint_interval).speed(1.0).clamp_range(1.0..=f64::INFINITY)).changed();
+						ui.label("Time between checkpoints (s of real time)");
+					});
+				});
+			});
 			ui.separator();
 			ui.heading("Image options");
 			anything_changed |= ui.checkbox(&mut self.settings.generate_image, "Generate the path image").on_hover_text("The image is really great for visualisation, but takes a while to generate which is not great when playing with the parameters.").changed();
@@ -85,11 +365,39 @@ impl application::Application {
 				self.settings.image_scale_factor = self.settings.image_scale_factor.max(0.01);
 				ui.label("Image scale factor").on_hover_text("This sets the resolution of the image, the default is 1024 by 760 pixels. This default is then multiplied on both of these axes by the scale factor.");
 			});
+			anything_changed |= ui.checkbox(&mut self.settings.image_draw_illumination, "Draw the illumination").on_hover_text("Shades the night side of the planet, draws the terminator and an arrow pointing towards the star.").changed();
+			ui.add_enabled_ui(self.settings.image_draw_illumination, |ui| {
+				anything_changed |= ui.checkbox(&mut self.settings.image_illumination_at_end, "Show the illumination at the end of the simulation").changed();
+				ui.add_enabled_ui(!self.settings.image_illumination_at_end, |ui| {
+					ui.horizontal(|ui| {
+						anything_changed |= ui.add(egui::DragValue::new(&mut self.settings.image_illumination_time).speed(100.0).clamp_range(0.0..=self.settings.simulation_time)).changed();
+						ui.label("Time to show the illumination at (s)");
+					});
+				});
+			});
 
-			if self.settings.recalculate_on_change && anything_changed {
+			// A running calculation is not interrupted, the changes are only used by the next one
+			if self.settings.recalculate_on_change && anything_changed && self.calculation_stage == message_passers::CalculationStage::End {
 				self.recalculate();
 			}
 		});
+        if self.settings != settings_before && !navigated_history {
+            self.settings_history.record_change(settings_before);
+        }
+        // Dragging a value or typing into it changes the settings every frame, the whole drag is a single undo step
+        if ctx.dragged_id().is_none() && ctx.memory(|memory| memory.focused().is_none()) {
+            self.settings_history.finish_edit(&self.settings);
+        }
         self.windows_opened.settings = opened;
     }
 }
+
+/// Like `egui::Ui::drag_angle`, but for an `f64` angle (rad), which keeps its full precision unless it is changed.
+fn drag_angle(ui: &mut egui::Ui, radians: &mut f64) -> egui::Response {
+	let mut degrees = radians.to_degrees();
+	let response = ui.add(egui::DragValue::new(&mut degrees).speed(1.0).suffix("°"));
+	if response.changed() {
+		*radians = degrees.to_radians();
+	}
+	response
+}
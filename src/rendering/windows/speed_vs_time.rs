@@ -0,0 +1,9 @@
+use eframe::egui;
+
+use crate::application;
+
+impl application::Application {
+    pub fn render_speed_vs_time_graph(&mut self, ui: &mut egui::Ui) {
+        self.render_time_graph(ui, "Speed vs time relationship", "m/s", |point| point.speed, None);
+    }
+}
@@ -0,0 +1,9 @@
+use eframe::egui;
+
+use crate::application;
+
+impl application::Application {
+    pub fn render_subsolar_bearing_vs_time_graph(&mut self, ui: &mut egui::Ui) {
+        self.render_time_graph(ui, "Subsolar bearing vs time relationship", "deg", |point| point.subsolar_bearing, None);
+    }
+}
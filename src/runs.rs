@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::{data, settings};
+
+/// A finished run kept around so it can be overlaid over other runs and compared with them.
+pub struct PinnedRun {
+    pub name: String,
+    pub visible: bool,
+    pub settings: settings::Settings,
+    pub data: HashMap<usize, Vec<data::Data>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RunSelection {
+    Current,
+    Pinned(usize),
+}
+
+/// Which two series are compared in the run difference graph.
+#[derive(Clone, Copy)]
+pub struct RunComparison {
+    pub first: RunSelection,
+    pub first_series: usize,
+    pub second: RunSelection,
+    pub second_series: usize,
+}
+
+impl Default for RunComparison {
+    fn default() -> Self {
+        Self {
+            first: RunSelection::Current,
+            first_series: 0,
+            second: RunSelection::Pinned(0),
+            second_series: 0,
+        }
+    }
+}
+
+impl RunComparison {
+    /// Keeps the selection pointing at the same runs after the `removed`-th pinned run is removed, a removed run is replaced by the current run.
+    pub fn remove_pinned(&mut self, removed: usize) {
+        for (selection, series) in [(&mut self.first, &mut self.first_series), (&mut self.second, &mut self.second_series)] {
+            match *selection {
+                RunSelection::Pinned(i) if i == removed => {
+                    *selection = RunSelection::Current;
+                    *series = 0;
+                }
+                RunSelection::Pinned(i) if i > removed => *selection = RunSelection::Pinned(i - 1),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the series of a run ordered by their index (by starting position and then by Marco's velocity), so they can be referred to by index.
+pub fn ordered_series(data: &HashMap<usize, Vec<data::Data>>) -> Vec<&Vec<data::Data>> {
+    let mut series = data.values().filter(|series| !series.is_empty()).collect::<Vec<&Vec<data::Data>>>();
+    series.sort_by_key(|series| series[0].series);
+    series
+}
+
+/// Calculates the difference in latitude and longitude (first - second, in degrees) at the times of the points of the first series.
+/// The second series is linearly interpolated between its points, times outside of its range are skipped.
+pub fn difference(first: &[data::Data], second: &[data::Data]) -> Vec<[f64; 3]> {
+    let mut differences = Vec::with_capacity(first.len());
+    if second.is_empty() {
+        return differences;
+    }
+    for point in first {
+        let index = second.partition_point(|other| other.time < point.time);
+        let (latitude, longitude) = if index < second.len() && second[index].time == point.time {
+            (second[index].latitude, second[index].longitude)
+        } else if index == 0 || index == second.len() {
+            continue;
+        } else {
+            let before = &second[index - 1];
+            let after = &second[index];
+            let t = (point.time - before.time) / (after.time - before.time);
+            // The longitude can jump by 360 degrees between two points, so the interpolation is done on the shortest way around
+            let longitude_step = normalise_longitude(after.longitude - before.longitude);
+            (before.latitude + (after.latitude - before.latitude) * t, before.longitude + longitude_step * t)
+        };
+        differences.push([point.time, point.latitude - latitude, normalise_longitude(point.longitude - longitude)]);
+    }
+    differences
+}
+
+/// Wraps a longitude difference (in degrees) into the range [-180; 180).
+pub fn normalise_longitude(longitude: f64) -> f64 {
+    (longitude + 180.0).rem_euclid(360.0) - 180.0
+}
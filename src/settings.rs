@@ -1,25 +1,172 @@
-#[derive(Clone, Copy)]
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{eclipse, error, precision, solar_position, terrain};
+
+/// Where the Marcos start from, every starting position is simulated with every velocity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StartPositions {
+    /// A single position given by `start_lat` and `start_lon`.
+    #[default]
+    Single,
+    /// A latitude/longitude grid given by `start_grid`.
+    Grid,
+    /// The latitudes of `start_grid` along the meridian of `start_lon`.
+    Meridian,
+    /// The coordinates in `start_list`.
+    List,
+}
+
+impl StartPositions {
+    pub const ALL: [Self; 4] = [Self::Single, Self::Grid, Self::Meridian, Self::List];
+}
+
+impl AsRef<str> for StartPositions {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::Single => "Single position",
+            Self::Grid => "Latitude/longitude grid",
+            Self::Meridian => "Along a meridian",
+            Self::List => "List of coordinates",
+        }
+    }
+}
+
+/// A grid of starting positions, the latitudes and longitudes (deg) are spread evenly from the minimum to the maximum.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StartGrid {
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    pub latitudes_count: usize,
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+    pub longitudes_count: usize,
+}
+
+impl Default for StartGrid {
+    fn default() -> Self {
+        Self {
+            min_latitude: -80.0,
+            max_latitude: 80.0,
+            latitudes_count: 9,
+            min_longitude: -180.0,
+            max_longitude: 150.0,
+            longitudes_count: 12,
+        }
+    }
+}
+
+impl StartGrid {
+    pub fn latitudes(&self) -> Vec<f64> {
+        spread(self.min_latitude, self.max_latitude, self.latitudes_count)
+    }
+
+    pub fn longitudes(&self) -> Vec<f64> {
+        spread(self.min_longitude, self.max_longitude, self.longitudes_count)
+    }
+}
+
+/// Spreads `count` values evenly from `min` to `max`, including both.
+pub fn spread(min: f64, max: f64, count: usize) -> Vec<f64> {
+    match count {
+        0 => Vec::new(),
+        1 => vec![min],
+        _ => (0..count).map(|i| min + (max - min) * (i as f64) / ((count - 1) as f64)).collect(),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Settings {
     pub recalculate_on_change: bool,
     pub generate_image: bool,
     pub image_scale_factor: f64,
+    pub image_draw_illumination: bool,
+    /// Draw the illumination at the end of the simulation instead of at `image_illumination_time`.
+    pub image_illumination_at_end: bool,
+    pub image_illumination_time: f64,
+    /// Write a checkpoint the simulation can be resumed from every `checkpoint_interval` (s of real time).
+    pub checkpoint: bool,
+    pub checkpoint_interval: f64,
 
     pub marco_min_velocity: f64,
     pub marco_max_velocity: f64,
     pub velocities_count: usize,
-    pub start_lat: f32,
-    pub start_lon: f32,
+    pub start_lat: f64,
+    pub start_lon: f64,
+    pub start_positions: StartPositions,
+    pub start_grid: StartGrid,
+    /// The starting positions as (latitude, longitude) pairs (deg).
+    pub start_list: Vec<(f64, f64)>,
+
+    /// Marco is a solar-powered rover whose speed follows from the energy available, his velocity being only the top speed.
+    pub rover_energy_model: bool,
+    /// The power (W) of the solar panels with the star in the zenith, it is proportional to the sine of the elevation of the star.
+    pub rover_solar_power: f64,
+    /// The capacity of the battery (Wh), which is full at the start.
+    pub rover_battery_capacity: f64,
+    /// The energy (J) needed to travel one metre, so the consumption (W) is this times the speed.
+    pub rover_energy_per_metre: f64,
+    /// The fraction of the battery capacity that has to be charged before a rover which ran out of energy starts moving again.
+    pub rover_min_charge: f64,
+
+    /// Marco only walks between `walking_window_start` and `walking_window_end` (h of local solar time), the window can go over midnight, but can not be empty.
+    pub walking_window: bool,
+    pub walking_window_start: f64,
+    pub walking_window_end: f64,
+    /// Marco walks for at most `max_walking_hours` (h) per local solar day.
+    pub limit_walking_hours: bool,
+    pub max_walking_hours: f64,
+    /// Marco rests while the star is higher than `zenith_rest_elevation` (deg) above his horizon.
+    pub zenith_rest: bool,
+    pub zenith_rest_elevation: f64,
+
+    /// Impassable terrain Marco can not walk into.
+    pub terrain_mask: Option<Arc<terrain::TerrainMask>>,
+    pub blocked_behaviour: terrain::BlockedBehaviour,
 
     pub planet_radius: f64,
-    pub rotational_axis_tilt: f32,
+    pub rotational_axis_tilt: f64,
+    /// Start the star where the Sun is seen from the Earth at `start_date`, instead of where `rotational_axis_tilt`, `orbital_phase` and `start_local_solar_time` put it.
+    /// The tilt of the axis then follows from the date too.
+    pub use_start_date: bool,
+    pub start_date: solar_position::UtcDateTime,
+    /// Where in its orbit the planet starts (deg), 0 being the northern summer solstice, 90 the northern autumn equinox and so on.
+    pub orbital_phase: f64,
+    /// Start at the local solar time `start_local_solar_time` (h) on the meridian of `start_lon`, instead of with the star above the prime meridian.
+    pub set_start_local_solar_time: bool,
+    pub start_local_solar_time: f64,
     pub rotational_period: f64,
+    /// Rings in the plane of the equator from `ring_inner_radius` to `ring_outer_radius` (km), whose shadow Marco does not walk in.
+    pub rings: bool,
+    pub ring_inner_radius: f64,
+    pub ring_outer_radius: f64,
+    /// The fraction of the starlight the rings block, 1 being completely opaque.
+    pub ring_opacity: f64,
 
     pub sun_distance: f64,
     pub orbital_period: f64,
+    /// The radius (km) of the star, which gives the size of its disc in eclipses.
+    pub star_radius: f64,
+
+    /// A moon on a circular orbit around the planet, which eclipses the star when it passes in front of it.
+    pub moon: bool,
+    /// The radius (km) of the orbit of the moon around the centre of the planet.
+    pub moon_orbit_radius: f64,
+    /// The sidereal orbital period of the moon (days), negative for a retrograde orbit.
+    pub moon_orbital_period: f64,
+    /// The inclination (deg) of the orbit of the moon to the ecliptic, the orbit crosses the ecliptic in the direction of the star at the start.
+    pub moon_inclination: f64,
+    /// How far (deg) the moon is ahead of the star along its orbit at the start, 0 being a new moon and 180 a full moon.
+    pub moon_phase: f64,
+    pub moon_radius: f64,
+    pub eclipse_behaviour: eclipse::EclipseBehaviour,
 
     pub timestep: f64,
     pub simulation_time: f64,
     pub points_to_show: usize,
+    /// The floating point type the positions are simulated in.
+    pub precision: precision::Precision,
 }
 
 impl Default for Settings {
@@ -28,23 +175,499 @@ impl Default for Settings {
             recalculate_on_change: false,
             generate_image: true,
             image_scale_factor: 20.0,
+            image_draw_illumination: true,
+            image_illumination_at_end: true,
+            image_illumination_time: 0.0,
+            checkpoint: false,
+            checkpoint_interval: 60.0,
 
             marco_min_velocity: 0.5 / 3.6,
             marco_max_velocity: 15.0 / 3.6,
             velocities_count: 1,
-            start_lat: 89.7_f32.to_radians(),
-            start_lon: -90.0_f32.to_radians(),
+            start_lat: 89.7_f64.to_radians(),
+            start_lon: -90.0_f64.to_radians(),
+            start_positions: StartPositions::default(),
+            start_grid: StartGrid::default(),
+            start_list: Vec::new(),
+
+            rover_energy_model: false,
+            rover_solar_power: 200.0,
+            rover_battery_capacity: 500.0,
+            rover_energy_per_metre: 100.0,
+            rover_min_charge: 0.2,
+
+            walking_window: false,
+            walking_window_start: 8.0,
+            walking_window_end: 18.0,
+            limit_walking_hours: false,
+            max_walking_hours: 8.0,
+            zenith_rest: false,
+            zenith_rest_elevation: 70.0,
+
+            terrain_mask: None,
+            blocked_behaviour: terrain::BlockedBehaviour::default(),
 
             planet_radius: 6000.0,
-            rotational_axis_tilt: 23.5_f32.to_radians(),
+            rotational_axis_tilt: 23.5_f64.to_radians(),
+            use_start_date: false,
+            start_date: solar_position::UtcDateTime::default(),
+            orbital_phase: 0.0,
+            set_start_local_solar_time: false,
+            start_local_solar_time: 6.0,
             rotational_period: 24.0,
+            rings: false,
+            ring_inner_radius: 7700.0,
+            ring_outer_radius: 14100.0,
+            ring_opacity: 1.0,
 
             sun_distance: 150.0 * 10.0_f64.powi(6),
             orbital_period: 1.0,
+            star_radius: 696000.0,
+
+            moon: false,
+            moon_orbit_radius: 384400.0,
+            moon_orbital_period: 27.321661,
+            moon_inclination: 5.145,
+            moon_phase: 0.0,
+            moon_radius: 1737.4,
+            eclipse_behaviour: eclipse::EclipseBehaviour::default(),
 
             timestep: 1.0,
             simulation_time: 86400.0,
             points_to_show: 1000,
+            precision: precision::Precision::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Checks that the simulation can be run with these settings.
+    pub fn validate(&self) -> Result<(), error::Error> {
+        let positive = [
+            (self.planet_radius, "The planet radius"),
+            (self.rotational_period, "The rotational period"),
+            (self.sun_distance, "The semi-major axis"),
+            (self.orbital_period.abs(), "The orbital period"),
+            (self.timestep, "The timestep"),
+            (self.image_scale_factor, "The image scale factor"),
+        ];
+        for (value, name) in positive {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(error::Error::InvalidSettings(format!("{name} has to be positive and finite, but it is {value}")));
+            }
+        }
+        if !self.simulation_time.is_finite() || self.simulation_time < 0.0 {
+            return Err(error::Error::InvalidSettings(format!(
+                "The time to simulate has to be a non-negative number, but it is {}",
+                self.simulation_time
+            )));
+        }
+        if !self.marco_min_velocity.is_finite() || !self.marco_max_velocity.is_finite() {
+            return Err(error::Error::InvalidSettings(String::from("Marco's velocities have to be finite")));
+        }
+        if self.rover_energy_model {
+            let positive = [(self.rover_battery_capacity, "The battery capacity"), (self.rover_energy_per_metre, "The energy needed per metre")];
+            for (value, name) in positive {
+                if !value.is_finite() || value <= 0.0 {
+                    return Err(error::Error::InvalidSettings(format!("{name} has to be positive, but it is {value}")));
+                }
+            }
+            if !self.rover_solar_power.is_finite() || self.rover_solar_power < 0.0 {
+                return Err(error::Error::InvalidSettings(format!(
+                    "The solar panel power has to be non-negative, but it is {}",
+                    self.rover_solar_power
+                )));
+            }
+            if !(0.0..=1.0).contains(&self.rover_min_charge) {
+                return Err(error::Error::InvalidSettings(format!(
+                    "The minimum charge has to be between 0 and 1, but it is {}",
+                    self.rover_min_charge
+                )));
+            }
+        }
+        if self.walking_window && (!(0.0..=24.0).contains(&self.walking_window_start) || !(0.0..=24.0).contains(&self.walking_window_end)) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The walking window has to be between 0 h and 24 h, but it is from {} h to {} h",
+                self.walking_window_start, self.walking_window_end
+            )));
+        }
+        if self.walking_window && self.walking_window_start == self.walking_window_end {
+            return Err(error::Error::InvalidSettings(format!(
+                "The walking window has to end at a different time than it starts, but it is from {} h to {} h",
+                self.walking_window_start, self.walking_window_end
+            )));
+        }
+        if self.limit_walking_hours && (self.max_walking_hours.is_nan() || self.max_walking_hours < 0.0) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The maximum walking hours per day have to be non-negative, but they are {}",
+                self.max_walking_hours
+            )));
+        }
+        if self.zenith_rest && !(0.0..=90.0).contains(&self.zenith_rest_elevation) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The elevation to rest above has to be between 0 deg and 90 deg, but it is {}",
+                self.zenith_rest_elevation
+            )));
         }
+        if self.start_positions_deg().is_empty() {
+            return Err(error::Error::InvalidSettings(String::from("At least one starting position is needed")));
+        }
+        if self
+            .start_positions_deg()
+            .iter()
+            .any(|(latitude, longitude)| !(-90.0..=90.0).contains(latitude) || !longitude.is_finite())
+        {
+            return Err(error::Error::InvalidSettings(String::from(
+                "The starting latitudes have to be between -90 deg and 90 deg and the longitudes have to be finite",
+            )));
+        }
+        if self.checkpoint && (self.checkpoint_interval.is_nan() || self.checkpoint_interval <= 0.0) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The checkpoint interval has to be positive, but it is {}",
+                self.checkpoint_interval
+            )));
+        }
+        if self.checkpoint && self.precision != precision::Precision::Double {
+            return Err(error::Error::InvalidSettings(String::from("Checkpoints can only be written in double precision")));
+        }
+        if self.use_start_date {
+            self.start_date.validate()?;
+            // Other periods would make the simulated Sun drift away from the real one day by day
+            let earth = Self::earth();
+            // Writing the periods as text and reading them back may change them slightly
+            let is_earths = |period: f64, earths: f64| (period - earths).abs() <= 1e-9 * earths;
+            if !is_earths(self.rotational_period, earth.rotational_period) || !is_earths(self.orbital_period, earth.orbital_period) {
+                return Err(error::Error::InvalidSettings(format!(
+                    "Starting at a date on the Earth needs the rotational period ({} h) and the orbital period ({} years) of the Earth",
+                    earth.rotational_period, earth.orbital_period
+                )));
+            }
+        }
+        if !self.orbital_phase.is_finite() {
+            return Err(error::Error::InvalidSettings(String::from("The orbital phase has to be finite")));
+        }
+        if self.set_start_local_solar_time && !(0.0..24.0).contains(&self.start_local_solar_time) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The local solar time at the start has to be between 0 h and 24 h, but it is {} h",
+                self.start_local_solar_time
+            )));
+        }
+        if self.rings && !(self.planet_radius <= self.ring_inner_radius && self.ring_inner_radius < self.ring_outer_radius && self.ring_outer_radius.is_finite()) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The rings have to go outwards from the surface of the planet, but they are from {} km to {} km",
+                self.ring_inner_radius, self.ring_outer_radius
+            )));
+        }
+        if self.rings && !(0.0..=1.0).contains(&self.ring_opacity) {
+            return Err(error::Error::InvalidSettings(format!(
+                "The opacity of the rings has to be between 0 and 1, but it is {}",
+                self.ring_opacity
+            )));
+        }
+        if self.moon {
+            let positive = [
+                (self.star_radius, "The radius of the star"),
+                (self.moon_orbital_period.abs(), "The orbital period of the moon"),
+                (self.moon_radius, "The radius of the moon"),
+            ];
+            for (value, name) in positive {
+                if !value.is_finite() || value <= 0.0 {
+                    return Err(error::Error::InvalidSettings(format!("{name} has to be positive, but it is {value}")));
+                }
+            }
+            if !self.moon_orbit_radius.is_finite() || self.moon_orbit_radius <= self.planet_radius + self.moon_radius {
+                return Err(error::Error::InvalidSettings(format!(
+                    "The moon has to orbit above the surface of the planet, but the radius of its orbit is {} km",
+                    self.moon_orbit_radius
+                )));
+            }
+            if !self.moon_inclination.is_finite() || !self.moon_phase.is_finite() {
+                return Err(error::Error::InvalidSettings(String::from("The inclination and the phase of the moon have to be finite")));
+            }
+        }
+        if self.points_to_show == 0 {
+            return Err(error::Error::InvalidSettings(String::from("At least one point has to be shown per velocity")));
+        }
+        Ok(())
+    }
+
+    /// The starting positions of the Marcos as (latitude, longitude) pairs (deg).
+    pub fn start_positions_deg(&self) -> Vec<(f64, f64)> {
+        let start_lat = self.start_lat.to_degrees();
+        let start_lon = self.start_lon.to_degrees();
+        match self.start_positions {
+            StartPositions::Single => vec![(start_lat, start_lon)],
+            StartPositions::Grid => {
+                let longitudes = self.start_grid.longitudes();
+                self.start_grid
+                    .latitudes()
+                    .into_iter()
+                    .flat_map(|latitude| longitudes.iter().map(move |longitude| (latitude, *longitude)))
+                    .collect()
+            }
+            StartPositions::Meridian => self.start_grid.latitudes().into_iter().map(|latitude| (latitude, start_lon)).collect(),
+            StartPositions::List => self.start_list.clone(),
+        }
+    }
+
+    /// The starting positions of the Marcos as (latitude, longitude) pairs (rad).
+    /// A single position is exactly `start_lat` and `start_lon`, without the round-off of converting them to degrees and back.
+    pub fn start_positions_rad(&self) -> Vec<(f64, f64)> {
+        match self.start_positions {
+            StartPositions::Single => vec![(self.start_lat, self.start_lon)],
+            _ => self
+                .start_positions_deg()
+                .into_iter()
+                .map(|(latitude, longitude)| (latitude.to_radians(), longitude.to_radians()))
+                .collect(),
+        }
+    }
+
+    /// The number of simulated series, one for every combination of a starting position and a velocity.
+    pub fn series_count(&self) -> usize {
+        self.start_positions_deg().len() * self.velocities_count.max(1)
+    }
+
+    /// Lists the settings as (key, value) pairs that can be read back by `from_key_values`.
+    /// The numbers are written so that they are read back exactly, the terrain mask is written as its path and loaded again.
+    pub fn to_key_values(&self) -> Vec<(&'static str, String)> {
+        let grid = &self.start_grid;
+        vec![
+            ("recalculate_on_change", self.recalculate_on_change.to_string()),
+            ("generate_image", self.generate_image.to_string()),
+            ("image_scale_factor", self.image_scale_factor.to_string()),
+            ("image_draw_illumination", self.image_draw_illumination.to_string()),
+            ("image_illumination_at_end", self.image_illumination_at_end.to_string()),
+            ("image_illumination_time", self.image_illumination_time.to_string()),
+            ("checkpoint", self.checkpoint.to_string()),
+            ("checkpoint_interval", self.checkpoint_interval.to_string()),
+            ("marco_min_velocity", self.marco_min_velocity.to_string()),
+            ("marco_max_velocity", self.marco_max_velocity.to_string()),
+            ("velocities_count", self.velocities_count.to_string()),
+            ("start_lat", self.start_lat.to_string()),
+            ("start_lon", self.start_lon.to_string()),
+            ("start_positions", format!("{:?}", self.start_positions)),
+            ("start_grid_min_latitude", grid.min_latitude.to_string()),
+            ("start_grid_max_latitude", grid.max_latitude.to_string()),
+            ("start_grid_latitudes_count", grid.latitudes_count.to_string()),
+            ("start_grid_min_longitude", grid.min_longitude.to_string()),
+            ("start_grid_max_longitude", grid.max_longitude.to_string()),
+            ("start_grid_longitudes_count", grid.longitudes_count.to_string()),
+            ("start_list", format_coordinates(&self.start_list).replace('\n', "; ")),
+            ("rover_energy_model", self.rover_energy_model.to_string()),
+            ("rover_solar_power", self.rover_solar_power.to_string()),
+            ("rover_battery_capacity", self.rover_battery_capacity.to_string()),
+            ("rover_energy_per_metre", self.rover_energy_per_metre.to_string()),
+            ("rover_min_charge", self.rover_min_charge.to_string()),
+            ("walking_window", self.walking_window.to_string()),
+            ("walking_window_start", self.walking_window_start.to_string()),
+            ("walking_window_end", self.walking_window_end.to_string()),
+            ("limit_walking_hours", self.limit_walking_hours.to_string()),
+            ("max_walking_hours", self.max_walking_hours.to_string()),
+            ("zenith_rest", self.zenith_rest.to_string()),
+            ("zenith_rest_elevation", self.zenith_rest_elevation.to_string()),
+            ("terrain_mask", self.terrain_mask.as_ref().map(|mask| mask.path.display().to_string()).unwrap_or_default()),
+            ("blocked_behaviour", format!("{:?}", self.blocked_behaviour)),
+            ("planet_radius", self.planet_radius.to_string()),
+            ("rotational_axis_tilt", self.rotational_axis_tilt.to_string()),
+            ("use_start_date", self.use_start_date.to_string()),
+            ("start_date", self.start_date.to_string()),
+            ("orbital_phase", self.orbital_phase.to_string()),
+            ("set_start_local_solar_time", self.set_start_local_solar_time.to_string()),
+            ("start_local_solar_time", self.start_local_solar_time.to_string()),
+            ("rotational_period", self.rotational_period.to_string()),
+            ("rings", self.rings.to_string()),
+            ("ring_inner_radius", self.ring_inner_radius.to_string()),
+            ("ring_outer_radius", self.ring_outer_radius.to_string()),
+            ("ring_opacity", self.ring_opacity.to_string()),
+            ("sun_distance", self.sun_distance.to_string()),
+            ("orbital_period", self.orbital_period.to_string()),
+            ("star_radius", self.star_radius.to_string()),
+            ("moon", self.moon.to_string()),
+            ("moon_orbit_radius", self.moon_orbit_radius.to_string()),
+            ("moon_orbital_period", self.moon_orbital_period.to_string()),
+            ("moon_inclination", self.moon_inclination.to_string()),
+            ("moon_phase", self.moon_phase.to_string()),
+            ("moon_radius", self.moon_radius.to_string()),
+            ("eclipse_behaviour", format!("{:?}", self.eclipse_behaviour)),
+            ("timestep", self.timestep.to_string()),
+            ("simulation_time", self.simulation_time.to_string()),
+            ("points_to_show", self.points_to_show.to_string()),
+            ("precision", format!("{:?}", self.precision)),
+        ]
+    }
+
+    /// Reads the settings from (key, value) pairs written by `to_key_values`, settings that are missing keep their default values.
+    pub fn from_key_values<'a>(key_values: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self, error::Error> {
+        fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, error::Error> {
+            value.parse().map_err(|_| error::Error::InvalidSettings(format!("\"{value}\" is not a valid value of {key}")))
+        }
+        fn parse_enum<T: Copy + fmt::Debug>(all: &[T], key: &str, value: &str) -> Result<T, error::Error> {
+            all.iter()
+                .copied()
+                .find(|variant| format!("{variant:?}") == value)
+                .ok_or_else(|| error::Error::InvalidSettings(format!("\"{value}\" is not a valid value of {key}")))
+        }
+
+        let mut settings = Self::default();
+        for (key, value) in key_values {
+            let grid = &mut settings.start_grid;
+            match key {
+                "recalculate_on_change" => settings.recalculate_on_change = parse(key, value)?,
+                "generate_image" => settings.generate_image = parse(key, value)?,
+                "image_scale_factor" => settings.image_scale_factor = parse(key, value)?,
+                "image_draw_illumination" => settings.image_draw_illumination = parse(key, value)?,
+                "image_illumination_at_end" => settings.image_illumination_at_end = parse(key, value)?,
+                "image_illumination_time" => settings.image_illumination_time = parse(key, value)?,
+                "checkpoint" => settings.checkpoint = parse(key, value)?,
+                "checkpoint_interval" => settings.checkpoint_interval = parse(key, value)?,
+                "marco_min_velocity" => settings.marco_min_velocity = parse(key, value)?,
+                "marco_max_velocity" => settings.marco_max_velocity = parse(key, value)?,
+                "velocities_count" => settings.velocities_count = parse(key, value)?,
+                "start_lat" => settings.start_lat = parse(key, value)?,
+                "start_lon" => settings.start_lon = parse(key, value)?,
+                "start_positions" => settings.start_positions = parse_enum(&StartPositions::ALL, key, value)?,
+                "start_grid_min_latitude" => grid.min_latitude = parse(key, value)?,
+                "start_grid_max_latitude" => grid.max_latitude = parse(key, value)?,
+                "start_grid_latitudes_count" => grid.latitudes_count = parse(key, value)?,
+                "start_grid_min_longitude" => grid.min_longitude = parse(key, value)?,
+                "start_grid_max_longitude" => grid.max_longitude = parse(key, value)?,
+                "start_grid_longitudes_count" => grid.longitudes_count = parse(key, value)?,
+                "start_list" => settings.start_list = parse_coordinates(&value.replace(';', "\n"))?,
+                "rover_energy_model" => settings.rover_energy_model = parse(key, value)?,
+                "rover_solar_power" => settings.rover_solar_power = parse(key, value)?,
+                "rover_battery_capacity" => settings.rover_battery_capacity = parse(key, value)?,
+                "rover_energy_per_metre" => settings.rover_energy_per_metre = parse(key, value)?,
+                "rover_min_charge" => settings.rover_min_charge = parse(key, value)?,
+                "walking_window" => settings.walking_window = parse(key, value)?,
+                "walking_window_start" => settings.walking_window_start = parse(key, value)?,
+                "walking_window_end" => settings.walking_window_end = parse(key, value)?,
+                "limit_walking_hours" => settings.limit_walking_hours = parse(key, value)?,
+                "max_walking_hours" => settings.max_walking_hours = parse(key, value)?,
+                "zenith_rest" => settings.zenith_rest = parse(key, value)?,
+                "zenith_rest_elevation" => settings.zenith_rest_elevation = parse(key, value)?,
+                "terrain_mask" if value.is_empty() => settings.terrain_mask = None,
+                "terrain_mask" => settings.terrain_mask = Some(Arc::new(terrain::TerrainMask::load(Path::new(value))?)),
+                "blocked_behaviour" => settings.blocked_behaviour = parse_enum(&terrain::BlockedBehaviour::ALL, key, value)?,
+                "planet_radius" => settings.planet_radius = parse(key, value)?,
+                "rotational_axis_tilt" => settings.rotational_axis_tilt = parse(key, value)?,
+                "use_start_date" => settings.use_start_date = parse(key, value)?,
+                "start_date" => settings.start_date = value.parse()?,
+                "orbital_phase" => settings.orbital_phase = parse(key, value)?,
+                "set_start_local_solar_time" => settings.set_start_local_solar_time = parse(key, value)?,
+                "start_local_solar_time" => settings.start_local_solar_time = parse(key, value)?,
+                "rotational_period" => settings.rotational_period = parse(key, value)?,
+                "rings" => settings.rings = parse(key, value)?,
+                "ring_inner_radius" => settings.ring_inner_radius = parse(key, value)?,
+                "ring_outer_radius" => settings.ring_outer_radius = parse(key, value)?,
+                "ring_opacity" => settings.ring_opacity = parse(key, value)?,
+                "sun_distance" => settings.sun_distance = parse(key, value)?,
+                "orbital_period" => settings.orbital_period = parse(key, value)?,
+                "star_radius" => settings.star_radius = parse(key, value)?,
+                "moon" => settings.moon = parse(key, value)?,
+                "moon_orbit_radius" => settings.moon_orbit_radius = parse(key, value)?,
+                "moon_orbital_period" => settings.moon_orbital_period = parse(key, value)?,
+                "moon_inclination" => settings.moon_inclination = parse(key, value)?,
+                "moon_phase" => settings.moon_phase = parse(key, value)?,
+                "moon_radius" => settings.moon_radius = parse(key, value)?,
+                "eclipse_behaviour" => settings.eclipse_behaviour = parse_enum(&eclipse::EclipseBehaviour::ALL, key, value)?,
+                "timestep" => settings.timestep = parse(key, value)?,
+                "simulation_time" => settings.simulation_time = parse(key, value)?,
+                "points_to_show" => settings.points_to_show = parse(key, value)?,
+                "precision" => settings.precision = parse_enum(&precision::Precision::ALL, key, value)?,
+                _ => return Err(error::Error::InvalidSettings(format!("Unknown setting {key}"))),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// The length of the solar day (from noon to noon) in seconds.
+    /// The star moves westwards because of the rotation of the planet and eastwards because of its orbit, so the solar day is longer than the sidereal one.
+    /// Returns infinity for a planet that always faces the star with the same side.
+    pub fn solar_day_length(&self) -> f64 {
+        let sidereal_day = self.rotational_period * 3600.0;
+        let year = self.orbital_period * 365.25 * 86400.0;
+        1.0 / (1.0 / sidereal_day - 1.0 / year).abs()
+    }
+
+    /// Sets the planet and its orbit to those of the Earth, the rotational period being the sidereal day and the orbital period the sidereal year.
+    pub fn apply_earth_preset(&mut self) {
+        self.planet_radius = 6371.0;
+        self.rotational_axis_tilt = 23.439_f64.to_radians();
+        self.rotational_period = 86164.0905 / 3600.0;
+        self.sun_distance = 149.597_870_7 * 10.0_f64.powi(6);
+        self.orbital_period = 365.256_363 / 365.25;
+    }
+
+    /// The default settings with the planet and the orbit of the Earth.
+    fn earth() -> Self {
+        let mut settings = Self::default();
+        settings.apply_earth_preset();
+        settings
+    }
+
+    /// Sets the planet, its orbit and its rings to those of Saturn, the rings going from the inner edge of the C ring to the outer edge of the A ring.
+    pub fn apply_saturn_preset(&mut self) {
+        self.planet_radius = 58232.0;
+        self.rotational_axis_tilt = 26.73_f64.to_radians();
+        self.rotational_period = 10.656;
+        self.sun_distance = 1433.53 * 10.0_f64.powi(6);
+        self.orbital_period = 10759.22 / 365.25;
+        self.rings = true;
+        self.ring_inner_radius = 74658.0;
+        self.ring_outer_radius = 136775.0;
+    }
+}
+
+/// Parses a list of starting positions, one "latitude, longitude" pair (deg) per line, empty lines are skipped.
+pub fn parse_coordinates(text: &str) -> Result<Vec<(f64, f64)>, error::Error> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let invalid = || error::Error::InvalidSettings(format!("\"{line}\" is not a \"latitude, longitude\" pair"));
+            let (latitude, longitude) = line.split_once(',').ok_or_else(invalid)?;
+            Ok((latitude.trim().parse().map_err(|_| invalid())?, longitude.trim().parse().map_err(|_| invalid())?))
+        })
+        .collect()
+}
+
+/// Formats a list of starting positions the way `parse_coordinates` reads them.
+pub fn format_coordinates(coordinates: &[(f64, f64)]) -> String {
+    coordinates.iter().map(|(latitude, longitude)| format!("{latitude}, {longitude}")).collect::<Vec<String>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn date_on_the_earth_needs_the_periods_of_the_earth() {
+        let mut settings = Settings {
+            use_start_date: true,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err(), "a 24 h sidereal day is not the Earth's");
+        settings.apply_earth_preset();
+        settings.validate().unwrap();
+        settings.rotational_period *= 1.0 + f64::EPSILON;
+        settings.validate().unwrap();
+        settings.orbital_period = 1.0;
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn infinite_lengths_are_rejected() {
+        Settings::default().validate().unwrap();
+        let settings = Settings {
+            timestep: f64::INFINITY,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
+        let settings = Settings {
+            planet_radius: f64::NAN,
+            ..Default::default()
+        };
+        assert!(settings.validate().is_err());
     }
 }
@@ -1,222 +1,1067 @@
-use std::f64::consts::PI;
+use std::path::Path;
 use std::sync::mpsc;
+use std::time::{Duration, Instant};
 
 use nalgebra::Vector3;
 
+use crate::checkpoint;
 use crate::data;
+use crate::eclipse;
+use crate::error;
+use crate::manifest;
 use crate::message_passers;
+#[cfg(feature = "image")]
+use crate::path_image;
+use crate::precision;
+use crate::progress;
 use crate::settings;
+use crate::solar_position;
+use crate::terrain;
 
-pub fn recalculate_simulation(settings: settings::Settings, sender: mpsc::Sender<message_passers::Message>) {
-    let velocities_count = settings.velocities_count.max(1);
-    let vels_count = velocities_count as f64;
+/// The state of a running simulation of all of Marco's velocities.
+/// The positions of Marco and the star are simulated in the floating point type `T`, everything else in `f64`.
+pub struct Simulation<T: precision::Float = f64> {
+    pub settings: settings::Settings,
+    pub time: f64,
+    pub marco_velocities: Vec<f64>,
+    pub marco_positions: Vec<Vector3<T>>,
+    /// The speed (m/s) every Marco moved at during the last step.
+    pub marco_speeds: Vec<f64>,
+    /// The energy (J) in the battery of every Marco, only used with the rover energy model.
+    pub battery_charges: Vec<f64>,
+    /// Whether the rover ran out of energy and waits for the minimum charge, only used with the rover energy model.
+    pub(crate) parked: Vec<bool>,
+    /// How long (s) every Marco has walked during his current local solar day.
+    pub(crate) walked_today: Vec<f64>,
+    /// The local solar time (h) of every Marco at the previous step, used to notice the start of a new day.
+    pub(crate) previous_local_solar_times: Vec<f64>,
+    pub sun_pos_norm: Vector3<T>,
+    pub ecliptic_axis: Vector3<T>,
+    /// The direction towards the moon from the centre of the planet and the axis of its orbit, only used with a moon.
+    pub moon_pos_norm: Vector3<T>,
+    pub moon_orbit_axis: Vector3<T>,
+    /// The eclipses that have ended, in the order they ended.
+    pub eclipses: Vec<eclipse::Eclipse>,
+    /// The eclipse every Marco is seeing at the moment, if any.
+    pub(crate) ongoing_eclipses: Vec<Option<eclipse::Eclipse>>,
+    planet_rotation_quaternion: precision::Rotation<T>,
+}
+
+impl<T: precision::Float> Simulation<T> {
+    pub fn new(settings: settings::Settings) -> Self {
+        let velocities_count = settings.velocities_count.max(1);
+        let vels_count = velocities_count as f64;
+
+        let velocities = (0..velocities_count)
+            .map(|i| settings.marco_min_velocity + (settings.marco_max_velocity - settings.marco_min_velocity) * ((i as f64) / vels_count))
+            .collect::<Vec<f64>>();
+        // Every starting position is simulated with every velocity, the series of one starting position are next to each other
+        let mut marco_velocities = Vec::new();
+        let mut marco_positions = Vec::new();
+        for (start_lat, start_lon) in settings.start_positions_rad() {
+            let (sin_lat, cos_lat) = T::from_f64(start_lat).sin_cos();
+            let (sin_lon, cos_lon) = T::from_f64(start_lon).sin_cos();
+            let start_pos = Vector3::new(cos_lat * cos_lon, cos_lat * sin_lon, sin_lat) * T::from_f64(settings.planet_radius);
+            marco_velocities.extend_from_slice(&velocities);
+            marco_positions.extend(std::iter::repeat_n(start_pos, velocities_count));
+        }
+        let series_count = marco_positions.len();
+        let (sun_pos_norm, ecliptic_axis) = initial_sun_state(&settings);
+        let (moon_pos_norm, moon_orbit_axis) = initial_moon_state(&settings, &sun_pos_norm, &ecliptic_axis);
+        let planet_rotation_axis = Vector3::new(T::zero(), T::zero(), T::from_f64(1.0));
+        let planet_rotation_quaternion =
+            precision::Rotation::new(planet_rotation_axis * (T::from_f64(2.0) * T::pi()) / T::from_f64(settings.rotational_period * 3600.0) * T::from_f64(settings.timestep) * T::from_f64(-1.0)); // Multiplied by -1 to make the star orbit the planet in the correct direction
+
+        Self {
+            time: 0.0,
+            marco_velocities,
+            marco_positions,
+            marco_speeds: vec![0.0; series_count],
+            battery_charges: vec![settings.rover_battery_capacity * 3600.0; series_count],
+            parked: vec![false; series_count],
+            walked_today: vec![0.0; series_count],
+            previous_local_solar_times: vec![0.0; series_count],
+            sun_pos_norm,
+            ecliptic_axis,
+            moon_pos_norm,
+            moon_orbit_axis,
+            eclipses: Vec::new(),
+            ongoing_eclipses: vec![None; series_count],
+            planet_rotation_quaternion,
+            settings,
+        }
+    }
+
+    /// The position (km) of the `i`-th Marco, rounded to `f64`.
+    pub fn position(&self, i: usize) -> Vector3<f64> {
+        self.marco_positions[i].map(T::to_f64)
+    }
+
+    /// The direction towards the star, rounded to `f64`.
+    pub fn sun_direction(&self) -> Vector3<f64> {
+        self.sun_pos_norm.map(T::to_f64)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time > self.settings.simulation_time
+    }
+
+    /// Whether the `i`-th Marco has daylight to walk in, whatever his rest schedule says.
+    pub fn is_in_daylight(&self, i: usize) -> bool {
+        self.light_allows_walking(self.star_elevation_sine(i), self.star_visible_fraction(i), self.is_in_ring_shadow(i))
+    }
+
+    /// Whether Marco can walk in the light of the star: it has to be above his horizon, he must not be in the shadow of the rings and the moon may only cover it as much as the eclipse behaviour allows.
+    fn light_allows_walking(&self, elevation_sine: f64, visible_fraction: f64, in_ring_shadow: bool) -> bool {
+        let eclipse_allows_walking = match self.settings.eclipse_behaviour {
+            eclipse::EclipseBehaviour::Stop => visible_fraction >= 1.0,
+            eclipse::EclipseBehaviour::Slow => visible_fraction > 0.0,
+        };
+        elevation_sine >= 0.0 && !in_ring_shadow && eclipse_allows_walking
+    }
+
+    /// The sine of the elevation of the star above the horizon of the `i`-th Marco.
+    pub fn star_elevation_sine(&self, i: usize) -> f64 {
+        let marco_pos = self.marco_positions[i];
+        let marco_to_sun = precision::normalize(&(self.sun_pos_norm * T::from_f64(self.settings.sun_distance) - marco_pos));
+        precision::dot(&marco_to_sun, &precision::normalize(&marco_pos)).to_f64()
+    }
+
+    /// The fraction of the disc of the star the moon does not cover as seen by the `i`-th Marco, always 1 without a moon.
+    pub fn star_visible_fraction(&self, i: usize) -> f64 {
+        if !self.settings.moon {
+            return 1.0;
+        }
+        let marco_pos = self.position(i);
+        let marco_to_sun = self.sun_direction() * self.settings.sun_distance - marco_pos;
+        let marco_to_moon = self.moon_pos_norm.map(T::to_f64) * self.settings.moon_orbit_radius - marco_pos;
+        // The moon is behind Marco
+        if marco_to_moon.dot(&marco_to_sun) <= 0.0 {
+            return 1.0;
+        }
+        let star_radius = (self.settings.star_radius / marco_to_sun.norm()).min(1.0).asin();
+        let moon_radius = (self.settings.moon_radius / marco_to_moon.norm()).min(1.0).asin();
+        1.0 - eclipse::covered_fraction(star_radius, moon_radius, marco_to_sun.angle(&marco_to_moon))
+    }
+
+    /// Whether the `i`-th Marco is in the shadow of the rings, never without rings.
+    pub fn is_in_ring_shadow(&self, i: usize) -> bool {
+        if !self.settings.rings {
+            return false;
+        }
+        let marco_pos = self.position(i);
+        let marco_to_sun = (self.sun_direction() * self.settings.sun_distance - marco_pos).normalize();
+        eclipse::is_in_ring_shadow(&marco_pos, &marco_to_sun, self.settings.ring_inner_radius, self.settings.ring_outer_radius)
+    }
+
+    /// The fraction of the starlight that reaches the `i`-th Marco through the rings.
+    pub fn ring_transmission(&self, i: usize) -> f64 {
+        if self.is_in_ring_shadow(i) {
+            1.0 - self.settings.ring_opacity
+        } else {
+            1.0
+        }
+    }
+
+    /// Keeps track of the eclipse the `i`-th Marco is seeing, an eclipse ends when the star is uncovered or sets.
+    fn record_eclipse(&mut self, i: usize, elevation_sine: f64, visible_fraction: f64) {
+        let coverage = 1.0 - visible_fraction;
+        if elevation_sine >= 0.0 && coverage > 0.0 {
+            let eclipse = self.ongoing_eclipses[i].get_or_insert(eclipse::Eclipse {
+                series: i,
+                start: self.time,
+                end: self.time,
+                max_coverage: 0.0,
+            });
+            eclipse.end = self.time;
+            eclipse.max_coverage = eclipse.max_coverage.max(coverage);
+        } else if let Some(eclipse) = self.ongoing_eclipses[i].take() {
+            self.eclipses.push(eclipse);
+        }
+    }
+
+    /// The local solar time (h) of the `i`-th Marco, 12 h being when the star crosses his meridian.
+    pub fn local_solar_time(&self, i: usize) -> f64 {
+        let (marco_pos, sun_pos_norm) = (self.position(i), self.sun_direction());
+        let hour_angle = marco_pos.y.atan2(marco_pos.x) - sun_pos_norm.y.atan2(sun_pos_norm.x);
+        (12.0 + hour_angle.to_degrees() / 15.0).rem_euclid(24.0)
+    }
+
+    /// Whether the rest schedule lets the `i`-th Marco walk during this step, given the sine of the elevation of the star.
+    fn may_walk(&mut self, i: usize, elevation_sine: f64) -> bool {
+        let settings = &self.settings;
+        let local_solar_time = self.local_solar_time(i);
+        // Walking westwards turns the local time back a little, only a jump by most of a day is the local midnight
+        if self.previous_local_solar_times[i] - local_solar_time > 12.0 {
+            self.walked_today[i] = 0.0;
+        }
+        self.previous_local_solar_times[i] = local_solar_time;
+
+        let in_window = if settings.walking_window_start <= settings.walking_window_end {
+            (settings.walking_window_start..settings.walking_window_end).contains(&local_solar_time)
+        } else {
+            local_solar_time >= settings.walking_window_start || local_solar_time < settings.walking_window_end
+        };
+        (!settings.walking_window || in_window)
+            && (!settings.limit_walking_hours || self.walked_today[i] + settings.timestep <= settings.max_walking_hours * 3600.0)
+            && (!settings.zenith_rest || elevation_sine <= settings.zenith_rest_elevation.to_radians().sin())
+    }
+
+    /// The fraction of the battery capacity the `i`-th Marco has left, always 1 without the rover energy model.
+    pub fn battery_charge(&self, i: usize) -> f64 {
+        if self.settings.rover_energy_model {
+            self.battery_charges[i] / (self.settings.rover_battery_capacity * 3600.0)
+        } else {
+            1.0
+        }
+    }
 
-    if let Err(err) = sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::Points)) {
-        println!("Error sending new stage (points): {err}");
+    /// Charges the battery of the `i`-th rover for one timestep and returns the speed (m/s) it can afford to move at.
+    /// The energy for the distance actually moved is taken from the battery by `discharge_rover`.
+    fn rover_speed(&mut self, i: usize, elevation_sine: f64, may_walk: bool) -> f64 {
+        let settings = &self.settings;
+        let capacity = settings.rover_battery_capacity * 3600.0;
+        let energy_per_step = settings.rover_energy_per_metre * settings.timestep;
+        self.battery_charges[i] += settings.rover_solar_power * elevation_sine.max(0.0) * settings.timestep;
+        if self.parked[i] && self.battery_charges[i] >= settings.rover_min_charge * capacity {
+            self.parked[i] = false;
+        }
+        if self.parked[i] || !may_walk {
+            return 0.0;
+        }
+        let speed = self.marco_velocities[i].min(self.battery_charges[i] / energy_per_step);
+        // The battery is empty, so the rover has to wait until it is charged enough
+        if speed < self.marco_velocities[i] {
+            self.parked[i] = true;
+        }
+        speed
+    }
+
+    /// Takes the energy for moving at `speed` (m/s) for one timestep from the battery of the `i`-th rover.
+    fn discharge_rover(&mut self, i: usize, speed: f64) {
+        let settings = &self.settings;
+        let used = speed * settings.rover_energy_per_metre * settings.timestep;
+        self.battery_charges[i] = (self.battery_charges[i] - used).clamp(0.0, settings.rover_battery_capacity * 3600.0);
+    }
+
+    /// Moves the `i`-th Marco towards the star by `distance` (m), avoiding impassable terrain.
+    /// Returns the distance (m) Marco actually moved.
+    fn walk(&mut self, i: usize, distance: f64) -> f64 {
+        let marco_pos = self.marco_positions[i];
+        let marco_pos_norm = precision::normalize(&marco_pos);
+        let towards_star_axis = precision::normalize(&marco_pos_norm.cross(&self.sun_pos_norm));
+        let angle = T::from_f64(distance / 1000.0 / self.settings.planet_radius);
+        let Some(terrain) = self.settings.terrain_mask.as_deref() else {
+            self.marco_positions[i] = precision::Rotation::new(towards_star_axis * angle).rotate(&marco_pos);
+            return distance;
+        };
+        // Marco can always walk out of impassable terrain he is in, he just can not enter it
+        let is_free = |position: &Vector3<T>| !terrain.is_blocked_at(&position.map(T::to_f64)) || terrain.is_blocked_at(&marco_pos.map(T::to_f64));
+        let deviations: &[f64] = match self.settings.blocked_behaviour {
+            terrain::BlockedBehaviour::Stop => &[0.0],
+            // The directions closest to the star first, the distance along the boundary is shorter the more Marco deviates
+            terrain::BlockedBehaviour::Slide => &[0.0, 15.0, -15.0, 30.0, -30.0, 45.0, -45.0, 60.0, -60.0, 75.0, -75.0],
+        };
+        for deviation in deviations {
+            let deviation = T::from_f64(deviation.to_radians());
+            let (_, deviation_cos) = deviation.sin_cos();
+            let axis = precision::Rotation::new(marco_pos_norm * deviation).rotate(&towards_star_axis);
+            let position = precision::Rotation::new(axis * angle * deviation_cos).rotate(&marco_pos);
+            if is_free(&position) {
+                self.marco_positions[i] = position;
+                return distance * deviation_cos.to_f64();
+            }
+        }
+        0.0
+    }
+
+    /// Moves every Marco and the star by one timestep.
+    pub fn step(&mut self) {
+        for i in 0..self.marco_positions.len() {
+            let elevation_sine = self.star_elevation_sine(i);
+            let visible_fraction = self.star_visible_fraction(i);
+            self.record_eclipse(i, elevation_sine, visible_fraction);
+            let slows_in_eclipses = self.settings.eclipse_behaviour == eclipse::EclipseBehaviour::Slow;
+            let ring_transmission = self.ring_transmission(i);
+            // Only move Marco when he has daylight, as `is_in_daylight` tells, and he is not resting
+            let may_walk = self.may_walk(i, elevation_sine) && self.light_allows_walking(elevation_sine, visible_fraction, self.is_in_ring_shadow(i));
+            let speed = if self.settings.rover_energy_model {
+                // The moon shades the solar panels in proportion to the part of the star it covers
+                self.rover_speed(i, elevation_sine * visible_fraction * ring_transmission, may_walk)
+            } else if may_walk && slows_in_eclipses {
+                self.marco_velocities[i] * visible_fraction
+            } else if may_walk {
+                self.marco_velocities[i]
+            } else {
+                0.0
+            };
+            let speed = if speed > 0.0 {
+                self.walk(i, speed * self.settings.timestep) / self.settings.timestep
+            } else {
+                0.0
+            };
+            if self.settings.rover_energy_model {
+                self.discharge_rover(i, speed);
+            }
+            if speed > 0.0 {
+                self.walked_today[i] += self.settings.timestep;
+            }
+            self.marco_speeds[i] = speed;
+        }
+
+        let settings = &self.settings;
+
+        // Everything that is not meant to be stationary with respect to the surface of the Earth has to be rotated in the opposite direction to the Earth if the surface of the Earth is to be stationary with respect to the coordinate system
+        self.sun_pos_norm = self.planet_rotation_quaternion.rotate(&self.sun_pos_norm);
+        self.ecliptic_axis = self.planet_rotation_quaternion.rotate(&self.ecliptic_axis);
+
+        let planet_orbit_quaternion =
+            precision::Rotation::new(self.ecliptic_axis * (T::from_f64(2.0) * T::pi()) / T::from_f64(settings.orbital_period * 365.25 * 86400.0) * T::from_f64(settings.timestep)); // Here we should not multiply by -1 as the direction of orbit of the planet around the star and of the star around the planet are the same
+        self.sun_pos_norm = planet_orbit_quaternion.rotate(&self.sun_pos_norm);
+
+        if settings.moon {
+            self.moon_pos_norm = self.planet_rotation_quaternion.rotate(&self.moon_pos_norm);
+            self.moon_orbit_axis = self.planet_rotation_quaternion.rotate(&self.moon_orbit_axis);
+            let moon_orbit_quaternion =
+                precision::Rotation::new(self.moon_orbit_axis * (T::from_f64(2.0) * T::pi()) / T::from_f64(settings.moon_orbital_period * 86400.0) * T::from_f64(settings.timestep));
+            self.moon_pos_norm = moon_orbit_quaternion.rotate(&self.moon_pos_norm);
+        }
+
+        self.time += settings.timestep;
+        if self.is_finished() {
+            // The eclipses still going on at the end are cut short by it
+            self.eclipses.extend(self.ongoing_eclipses.iter_mut().filter_map(Option::take));
+        }
     }
-    let mut data = vec![Vec::new(); velocities_count];
-    let mut time = 0.0;
-    let start_lat = settings.start_lat as f64;
-    let start_lon = settings.start_lon as f64;
-    let axis_tilt = PI / 2.0 - settings.rotational_axis_tilt as f64;
 
-    let mut marco_positions = vec![Vector3::new(start_lat.cos() * start_lon.cos(), start_lat.cos() * start_lon.sin(), start_lat.sin()) * settings.planet_radius; velocities_count];
-    let mut sun_pos_norm = Vector3::new(axis_tilt.sin(), 0.0, axis_tilt.cos());
+    pub fn point(&self, i: usize, colour: data::Colour) -> data::Data {
+        data::Data {
+            series: i,
+            speed: self.marco_speeds[i],
+            battery_charge: self.battery_charge(i),
+            ..data::Data::from_raw(self.position(i), self.sun_direction(), self.time, self.settings.planet_radius, self.marco_velocities[i], colour)
+        }
+    }
+}
 
-    let mut ecliptic_axis = sun_pos_norm.cross(&Vector3::new(0.0, 1.0, 0.0)).normalize();
-    let planet_rotation_axis = Vector3::new(0.0, 0.0, 1.0).normalize();
-    let planet_rotation_quaternion = nalgebra::UnitQuaternion::new(planet_rotation_axis * (2.0 * PI) / (settings.rotational_period * 3600.0) * settings.timestep * (-1.0)); // Multiplied by -1 to make the star orbit the planet in the correct direction
+/// Returns the direction towards the star and the axis of the orbit of the planet (the normal to the ecliptic) at the start of the simulation.
+pub fn initial_sun_state<T: precision::Float>(settings: &settings::Settings) -> (Vector3<T>, Vector3<T>) {
+    if settings.use_start_date {
+        let (sun_pos_norm, ecliptic_axis) = solar_position::SolarPosition::at(&settings.start_date).directions();
+        return (sun_pos_norm.map(T::from_f64), ecliptic_axis.map(T::from_f64));
+    }
+    // At the northern summer solstice, with the star above the prime meridian
+    let (sin, cos) = (T::pi() / T::from_f64(2.0) - T::from_f64(settings.rotational_axis_tilt)).sin_cos();
+    let sun_pos_norm = Vector3::new(sin, T::zero(), cos);
+    let ecliptic_axis = precision::normalize(&sun_pos_norm.cross(&Vector3::new(T::zero(), T::from_f64(1.0), T::zero())));
+    // The star moves along the orbit the same way as in `Simulation::step`
+    let sun_pos_norm = precision::Rotation::new(ecliptic_axis * T::from_f64(settings.orbital_phase.to_radians())).rotate(&sun_pos_norm);
+    if !settings.set_start_local_solar_time {
+        return (sun_pos_norm, ecliptic_axis);
+    }
+    // The local solar time is 12 h plus the angle (15 deg per hour) the star is west of the meridian
+    let sun_longitude = sun_pos_norm.y.to_f64().atan2(sun_pos_norm.x.to_f64());
+    let target_longitude = settings.start_lon - ((settings.start_local_solar_time - 12.0) * 15.0).to_radians();
+    let rotation = precision::Rotation::new(Vector3::new(T::zero(), T::zero(), T::from_f64(target_longitude - sun_longitude)));
+    (rotation.rotate(&sun_pos_norm), rotation.rotate(&ecliptic_axis))
+}
+
+/// Returns the direction towards the moon and the axis of its orbit at the start of the simulation, given those of the star and of the orbit of the planet.
+pub fn initial_moon_state<T: precision::Float>(settings: &settings::Settings, sun_pos_norm: &Vector3<T>, ecliptic_axis: &Vector3<T>) -> (Vector3<T>, Vector3<T>) {
+    // The orbit is tilted about the direction of the star, so the star is on both the ecliptic and the orbit of the moon
+    let moon_orbit_axis = precision::Rotation::new(sun_pos_norm * T::from_f64(settings.moon_inclination.to_radians())).rotate(ecliptic_axis);
+    let moon_pos_norm = precision::Rotation::new(moon_orbit_axis * T::from_f64(settings.moon_phase.to_radians())).rotate(sun_pos_norm);
+    (moon_pos_norm, moon_orbit_axis)
+}
 
+/// The colours of the series of the individual velocities, going from red for the slowest to blue for the fastest.
+pub fn colours(velocities_count: usize) -> Vec<data::Colour> {
+    let vels_count = velocities_count as f64;
     let red = hsluv::rgb_to_hsluv(1.0, 0.0, 0.0);
     let blue = hsluv::rgb_to_hsluv(0.0, 0.0, 1.0);
-    let colours = (0..velocities_count)
+    (0..velocities_count)
         .map(|i| {
             let vel_i = i as f32;
             let rgb = hsluv::hsluv_to_rgb(red.0 + (blue.0 - red.0) * (vel_i / vels_count as f32) as f64, red.1, red.2);
-            eframe::egui::Color32::from_rgba_unmultiplied((rgb.0 * 255.0) as u8, (rgb.1 * 255.0) as u8, (rgb.2 * 255.0) as u8, ((1.0 / vels_count as f32).max(1.0) * 255.0) as u8)
+            data::Colour::new((rgb.0 * 255.0) as u8, (rgb.1 * 255.0) as u8, (rgb.2 * 255.0) as u8, ((1.0 / vels_count as f32).max(1.0) * 255.0) as u8)
         })
-        .collect::<Vec<eframe::egui::Color32>>();
+        .collect::<Vec<data::Colour>>()
+}
 
-    for i in 0..velocities_count {
-        let point = data::Data::from_raw(marco_positions[i], time, settings.planet_radius, colours[i]);
-        data[i].push(point.clone());
-        if let Err(err) = sender.send(message_passers::Message::NewPoint(point.clone())) {
-            println!("Error sending new point: {err}\nPoint: {:?}", point);
+/// Runs the whole simulation, calling `on_point` with the index of the series and the point for every point as soon as it is sampled and `on_step` with the simulation and the points sampled so far after every step.
+/// Returns the sampled points of every series, or the first error returned by any of the callbacks.
+/// The simulation is in double precision, whatever `settings.precision` is, other precisions are simulated by `continue_simulation` with a `Simulation<T>`.
+pub fn simulate(
+    settings: settings::Settings,
+    on_point: impl FnMut(usize, &data::Data) -> Result<(), error::Error>,
+    on_step: impl FnMut(&Simulation, &[Vec<data::Data>]) -> Result<(), error::Error>,
+) -> Result<Vec<Vec<data::Data>>, error::Error> {
+    continue_simulation(Simulation::<f64>::new(settings), Vec::new(), on_point, on_step)
+}
+
+/// Runs the rest of a simulation, such as one resumed from a checkpoint, `data` being the points sampled so far.
+/// If `data` is empty, the simulation is a new one and its starting points are sampled first. The callbacks are the same as in `simulate`.
+pub fn continue_simulation<T: precision::Float>(
+    mut simulation: Simulation<T>,
+    mut data: Vec<Vec<data::Data>>,
+    mut on_point: impl FnMut(usize, &data::Data) -> Result<(), error::Error>,
+    mut on_step: impl FnMut(&Simulation<T>, &[Vec<data::Data>]) -> Result<(), error::Error>,
+) -> Result<Vec<Vec<data::Data>>, error::Error> {
+    let velocities_count = simulation.settings.velocities_count.max(1);
+    let (simulation_time, points_to_show) = (simulation.settings.simulation_time, simulation.settings.points_to_show);
+    // The series are coloured by their velocity, so the series of different starting positions with the same velocity share a colour
+    let colours = colours(velocities_count);
+    let colour = |i: usize| colours[i % velocities_count];
+
+    if data.is_empty() {
+        data = vec![Vec::new(); simulation.marco_positions.len()];
+        for (i, series) in data.iter_mut().enumerate() {
+            let point = simulation.point(i, colour(i));
+            on_point(i, &point)?;
+            series.push(point);
+        }
+    }
+
+    while !simulation.is_finished() {
+        simulation.step();
+        for (i, series) in data.iter_mut().enumerate() {
+            if simulation.time / simulation_time > (series.len() as f64) / (points_to_show as f64) {
+                let point = simulation.point(i, colour(i));
+                on_point(i, &point)?;
+                series.push(point);
+            }
         }
+        // The points of this step are already sampled, so the simulation can be resumed from this state with the next step
+        on_step(&simulation, &data)?;
+    }
+    for (i, series) in data.iter_mut().enumerate() {
+        let point = simulation.point(i, colour(i));
+        on_point(i, &point)?;
+        series.push(point);
     }
+    Ok(data)
+}
 
-    while time <= settings.simulation_time {
-        for i in 0..velocities_count {
-            let marco_vel = settings.marco_min_velocity + (settings.marco_max_velocity - settings.marco_min_velocity) * ((i as f64) / vels_count);
-            let marco_to_sun = (settings.sun_distance * sun_pos_norm - marco_positions[i]).normalize();
+/// Points are sent to the GUI in batches of at most this many points.
+const BATCH_SIZE: usize = 4096;
+/// Points are sent to the GUI at least this often, so it does not wait for a full batch in slow simulations.
+const BATCH_INTERVAL: Duration = Duration::from_millis(50);
 
-            // Only move Marco when the star is above his horizon
-            if marco_to_sun.dot(&marco_positions[i]) >= 0.0 {
-                let rotation_axis = (marco_positions[i].normalize().cross(&sun_pos_norm)).normalize() * (marco_vel / 1000.0 * settings.timestep) / settings.planet_radius;
-                let rotation_quaternion = nalgebra::UnitQuaternion::new(rotation_axis);
+/// Collects sampled points and sends them to the GUI in batches, grouped by series.
+struct PointBatcher<'a> {
+    sender: &'a mpsc::SyncSender<message_passers::Message>,
+    batch: Vec<Vec<data::Data>>,
+    points: usize,
+    last_flush: Instant,
+}
 
-                marco_positions[i] = rotation_quaternion * marco_positions[i];
+impl<'a> PointBatcher<'a> {
+    fn new(sender: &'a mpsc::SyncSender<message_passers::Message>, series_count: usize) -> Self {
+        Self {
+            sender,
+            batch: vec![Vec::new(); series_count],
+            points: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    fn push(&mut self, i: usize, point: data::Data) -> Result<(), error::Error> {
+        self.batch[i].push(point);
+        self.points += 1;
+        if self.points >= BATCH_SIZE || self.last_flush.elapsed() >= BATCH_INTERVAL {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), error::Error> {
+        self.last_flush = Instant::now();
+        if self.points == 0 {
+            return Ok(());
+        }
+        let series_count = self.batch.len();
+        let batch = std::mem::replace(&mut self.batch, vec![Vec::new(); series_count]);
+        self.points = 0;
+        // The channel is bounded, so this blocks when the GUI can not keep up
+        self.sender.send(message_passers::Message::NewPoints(batch))?;
+        Ok(())
+    }
+}
+
+pub fn recalculate_simulation(settings: settings::Settings, sender: mpsc::SyncSender<message_passers::Message>) {
+    let result = settings
+        .validate()
+        // The checkpoint of an earlier run is only replaced by a run that writes its own, the GUI asks before that
+        .and_then(|()| if settings.checkpoint { checkpoint::remove(&checkpoint_path()) } else { Ok(()) })
+        .and_then(|()| match settings.precision {
+            // Checkpoints are only written in double precision, which the validation of the settings ensures
+            precision::Precision::Single => run_new_calculation(Simulation::<f32>::new(settings), &sender, |_, _, _| Ok(())),
+            precision::Precision::Double => run_new_calculation(Simulation::<f64>::new(settings), &sender, write_checkpoint),
+            precision::Precision::DoubleDouble => run_new_calculation(Simulation::<precision::DoubleDouble>::new(settings), &sender, |_, _, _| Ok(())),
+        });
+    message_passers::finish_calculation(&sender, result);
+}
+
+/// Continues the simulation saved in the checkpoint, sending the points sampled before the checkpoint first.
+pub fn resume_simulation(checkpoint: checkpoint::Checkpoint, sender: mpsc::SyncSender<message_passers::Message>) {
+    let result = checkpoint.simulation.settings.validate().and_then(|()| {
+        if !checkpoint.data.is_empty() {
+            sender.send(message_passers::Message::NewPoints(checkpoint.data.clone()))?;
+        }
+        for eclipse in &checkpoint.simulation.eclipses {
+            sender.send(message_passers::Message::Eclipse(*eclipse))?;
+        }
+        run_calculation(checkpoint.simulation, checkpoint.data, checkpoint.illuminating_sun_pos_norm, true, &sender, write_checkpoint)
+    });
+    message_passers::finish_calculation(&sender, result);
+}
+
+fn run_new_calculation<T: precision::Float>(
+    simulation: Simulation<T>,
+    sender: &mpsc::SyncSender<message_passers::Message>,
+    write_checkpoint: impl FnMut(&Simulation<T>, &[Vec<data::Data>], Vector3<f64>) -> Result<(), error::Error>,
+) -> Result<(), error::Error> {
+    let illuminating_sun_pos_norm = simulation.sun_direction();
+    run_calculation(simulation, Vec::new(), illuminating_sun_pos_norm, false, sender, write_checkpoint)
+}
+
+/// The path of the checkpoint in the output directory, which the GUI resumes from.
+pub fn checkpoint_path() -> std::path::PathBuf {
+    Path::new(crate::OUTPUT_DIRECTORY).join(checkpoint::CHECKPOINT_FILE_NAME)
+}
+
+fn write_checkpoint(simulation: &Simulation, data: &[Vec<data::Data>], illuminating_sun_pos_norm: Vector3<f64>) -> Result<(), error::Error> {
+    checkpoint::write(&checkpoint_path(), simulation, data, illuminating_sun_pos_norm)
+}
+
+/// Runs the simulation and generates the image, `resumed` telling whether the simulation is continued from a checkpoint, which is recorded in the manifest of the image.
+/// `write_checkpoint` is called every `checkpoint_interval` when checkpoints are enabled.
+fn run_calculation<T: precision::Float>(
+    simulation: Simulation<T>,
+    data: Vec<Vec<data::Data>>,
+    mut illuminating_sun_pos_norm: Vector3<f64>,
+    resumed: bool,
+    sender: &mpsc::SyncSender<message_passers::Message>,
+    mut write_checkpoint: impl FnMut(&Simulation<T>, &[Vec<data::Data>], Vector3<f64>) -> Result<(), error::Error>,
+) -> Result<(), error::Error> {
+    let started = Instant::now();
+    let settings = simulation.settings.clone();
+    sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::Points))?;
+    // The time simulated before a checkpoint took no time in this session
+    let mut reporter = progress::ProgressReporter::resumed(simulation.time);
+    let mut batcher = PointBatcher::new(sender, settings.series_count());
+    let mut last_checkpoint = Instant::now();
+    // The eclipses of a resumed simulation that ended before the checkpoint are sent by `resume_simulation`
+    let mut eclipses_sent = simulation.eclipses.len();
+    let data = continue_simulation(
+        simulation,
+        data,
+        |i, point| batcher.push(i, point.clone()),
+        |simulation, data| {
+            if settings.image_illumination_at_end || simulation.time <= settings.image_illumination_time {
+                illuminating_sun_pos_norm = simulation.sun_direction();
+            }
+            for eclipse in &simulation.eclipses[eclipses_sent..] {
+                sender.send(message_passers::Message::Eclipse(*eclipse))?;
             }
+            eclipses_sent = simulation.eclipses.len();
+            if let Some(progress) = reporter.step(simulation.time, settings.simulation_time) {
+                sender.send(message_passers::Message::Progress(progress))?;
+            }
+            if settings.checkpoint && last_checkpoint.elapsed().as_secs_f64() >= settings.checkpoint_interval {
+                write_checkpoint(simulation, data, illuminating_sun_pos_norm)?;
+                last_checkpoint = Instant::now();
+            }
+            Ok(())
+        },
+    )?;
+    batcher.flush()?;
+    if settings.generate_image {
+        let sun_pos_norm = settings.image_draw_illumination.then_some(illuminating_sun_pos_norm);
+        let path = generate_image(data, settings.clone(), sun_pos_norm, sender)?;
+        // The duration of a resumed simulation is only the part after the checkpoint
+        manifest::write(&path, &settings, &[("resumed_from_checkpoint", resumed.to_string())], Some(started.elapsed()))?;
+        sender.send(message_passers::Message::ImageSaved(path))?;
+    }
+    // The run is finished, so resuming it would only simulate its end again
+    if settings.checkpoint {
+        checkpoint::remove(&checkpoint_path())?;
+    }
+    Ok(())
+}
 
-            if time / settings.simulation_time > (data[i].len() as f64) / (settings.points_to_show as f64) {
-                let point = data::Data::from_raw(marco_positions[i], time, settings.planet_radius, colours[i]);
-                data[i].push(point.clone());
-                if let Err(err) = sender.send(message_passers::Message::NewPoint(point.clone())) {
-                    println!("Error sending new point: {err}\nPoint: {:?}", point);
-                }
+#[cfg(feature = "image")]
+fn generate_image(
+    data: Vec<Vec<data::Data>>,
+    settings: settings::Settings,
+    sun_pos_norm: Option<Vector3<f64>>,
+    sender: &mpsc::SyncSender<message_passers::Message>,
+) -> Result<std::path::PathBuf, error::Error> {
+    sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::Plots))?;
+    let mut reporter = progress::ProgressReporter::new();
+    path_image::generate_image(data, settings, sun_pos_norm, |done, total| {
+        if let Some(progress) = reporter.step(done, total) {
+            sender.send(message_passers::Message::Progress(progress))?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(not(feature = "image"))]
+fn generate_image(
+    _data: Vec<Vec<data::Data>>,
+    _settings: settings::Settings,
+    _sun_pos_norm: Option<Vector3<f64>>,
+    _sender: &mpsc::SyncSender<message_passers::Message>,
+) -> Result<std::path::PathBuf, error::Error> {
+    Err(error::Error::InvalidSettings(String::from(
+        "The path image can not be generated, the program was compiled without the `image` feature",
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A period (h or yr) so long that the motion it causes stays below the tolerances of the tests.
+    const NEVER: f64 = 1e15;
+
+    fn test_settings() -> settings::Settings {
+        settings::Settings {
+            generate_image: false,
+            marco_min_velocity: 5.0,
+            marco_max_velocity: 5.0,
+            velocities_count: 1,
+            start_lat: 30.0_f64.to_radians(),
+            start_lon: 0.0,
+            timestep: 10.0,
+            simulation_time: 86400.0,
+            points_to_show: 100,
+            ..Default::default()
+        }
+    }
+
+    fn longitude_difference(a: f64, b: f64) -> f64 {
+        (a - b + 180.0).rem_euclid(360.0) - 180.0
+    }
+
+    #[test]
+    fn stationary_marco_stays_put() {
+        let settings = settings::Settings {
+            marco_min_velocity: 0.0,
+            marco_max_velocity: 0.0,
+            ..test_settings()
+        };
+        let data = simulate(settings.clone(), |_, _| Ok(()), |_, _| Ok(())).unwrap();
+        let start = &data[0][0];
+        for point in &data[0] {
+            assert!((point.latitude - start.latitude).abs() < 1e-9, "latitude drifted to {}", point.latitude);
+            assert!(longitude_difference(point.longitude, start.longitude).abs() < 1e-9, "longitude drifted to {}", point.longitude);
+        }
+    }
+
+    #[test]
+    fn marco_on_the_equator_stays_on_the_equator_without_tilt() {
+        let settings = settings::Settings {
+            start_lat: 0.0,
+            start_lon: (-60.0_f64).to_radians(),
+            rotational_axis_tilt: 0.0,
+            simulation_time: 3.0 * 86400.0,
+            ..test_settings()
+        };
+        let data = simulate(settings.clone(), |_, _| Ok(()), |_, _| Ok(())).unwrap();
+        // Marco walks east in the morning and west in the afternoon, so he only oscillates around his starting point
+        assert!(data[0].iter().any(|point| longitude_difference(point.longitude, -60.0).abs() > 0.1), "Marco did not move at all");
+        for point in &data[0] {
+            assert!(point.latitude.abs() < 1e-6, "Marco left the equator: {} deg at {} s", point.latitude, point.time);
+        }
+    }
+
+    #[test]
+    fn marco_stays_on_the_sphere() {
+        let settings = settings::Settings {
+            marco_min_velocity: 1.0,
+            marco_max_velocity: 50.0,
+            velocities_count: 5,
+            ..test_settings()
+        };
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        while !simulation.is_finished() {
+            simulation.step();
+            for marco_pos in &simulation.marco_positions {
+                assert!(
+                    (marco_pos.norm() - settings.planet_radius).abs() < 1e-6 * settings.planet_radius,
+                    "Marco left the surface: |r| = {}",
+                    marco_pos.norm()
+                );
             }
         }
+    }
 
-        // Everything that is not meant to be stationary with respect to the surface of the Earth has to be rotated in the opposite direction to the Earth if the surface of the Earth is to be stationary with respect to the coordinate system
-        sun_pos_norm = planet_rotation_quaternion * sun_pos_norm;
-        ecliptic_axis = planet_rotation_quaternion * ecliptic_axis;
+    #[test]
+    fn star_returns_after_a_sidereal_period() {
+        let settings = settings::Settings {
+            timestep: 1.0,
+            orbital_period: NEVER,
+            ..test_settings()
+        };
+        settings.validate().unwrap();
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        let start = simulation.sun_pos_norm;
+        let steps = (settings.rotational_period * 3600.0 / settings.timestep).round() as usize;
+        for _ in 0..steps {
+            simulation.step();
+        }
+        assert!(
+            (simulation.sun_pos_norm - start).norm() < 1e-9,
+            "the star ended at {:?} instead of {:?}",
+            simulation.sun_pos_norm,
+            start
+        );
+    }
 
-        let planet_orbit_quaternion = nalgebra::UnitQuaternion::new(ecliptic_axis * (2.0 * PI) / (settings.orbital_period * 365.25 * 86400.0) * settings.timestep); // Here we should not multiply by -1 as the direction of orbit of the planet around the star and of the star around the planet are the same
-        sun_pos_norm = planet_orbit_quaternion * sun_pos_norm;
+    #[test]
+    fn star_moves_westwards_during_the_day() {
+        let settings = settings::Settings {
+            rotational_axis_tilt: 0.0,
+            orbital_period: NEVER,
+            ..test_settings()
+        };
+        settings.validate().unwrap();
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        let steps = (settings.rotational_period * 3600.0 / 4.0 / settings.timestep).round() as usize;
+        for _ in 0..steps {
+            simulation.step();
+        }
+        let sun_longitude = simulation.sun_pos_norm.y.atan2(simulation.sun_pos_norm.x).to_degrees();
+        assert!(longitude_difference(sun_longitude, -90.0).abs() < 1e-6, "the star is at {sun_longitude} deg after a quarter of a day");
+    }
+
+    #[test]
+    fn star_moves_eastwards_during_the_year() {
+        let settings = settings::Settings {
+            rotational_axis_tilt: 0.0,
+            rotational_period: NEVER,
+            timestep: 1800.0,
+            ..test_settings()
+        };
+        settings.validate().unwrap();
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        let steps = (settings.orbital_period * 365.25 * 86400.0 / 4.0 / settings.timestep).round() as usize;
+        for _ in 0..steps {
+            simulation.step();
+        }
+        let sun_longitude = simulation.sun_pos_norm.y.atan2(simulation.sun_pos_norm.x).to_degrees();
+        assert!(longitude_difference(sun_longitude, 90.0).abs() < 1e-6, "the star is at {sun_longitude} deg after a quarter of a year");
+    }
 
-        time += settings.timestep;
+    #[test]
+    fn marco_walks_towards_the_star() {
+        let mut simulation = Simulation::<f64>::new(settings::Settings {
+            start_lat: 0.0,
+            start_lon: (-30.0_f64).to_radians(),
+            rotational_axis_tilt: 0.0,
+            ..test_settings()
+        });
+        let angle_to_star = |simulation: &Simulation| simulation.marco_positions[0].normalize().dot(&simulation.sun_pos_norm).acos();
+        let before = angle_to_star(&simulation);
+        let marco_before = simulation.marco_positions[0];
+        simulation.step();
+        // Compare against the star's position before it moved
+        simulation.sun_pos_norm = Simulation::<f64>::new(simulation.settings.clone()).sun_pos_norm;
+        assert!(angle_to_star(&simulation) < before);
+        let walked = (simulation.marco_positions[0] - marco_before).norm() * 1000.0;
+        let expected = simulation.settings.marco_max_velocity * simulation.settings.timestep;
+        assert!((walked - expected).abs() < 1e-6 * expected, "Marco walked {walked} m instead of {expected} m");
     }
-    for i in 0..velocities_count {
-        let point = data::Data::from_raw(marco_positions[i], time, settings.planet_radius, colours[i]);
-        data[i].push(point.clone());
-        if let Err(err) = sender.send(message_passers::Message::NewPoint(point.clone())) {
-            println!("Error sending new point: {err}\nPoint: {:?}", point);
+
+    #[test]
+    fn star_is_east_of_marco_at_sunrise() {
+        let simulation = Simulation::<f64>::new(settings::Settings {
+            start_lat: 0.0,
+            start_lon: (-90.0_f64).to_radians(),
+            rotational_axis_tilt: 0.0,
+            ..test_settings()
+        });
+        let point = simulation.point(0, data::Colour::default());
+        assert!((point.subsolar_distance - 90.0).abs() < 1e-5, "the subsolar point is {} deg away", point.subsolar_distance);
+        assert!((point.subsolar_bearing - 90.0).abs() < 1e-5, "the subsolar point is at a bearing of {} deg", point.subsolar_bearing);
+    }
+
+    #[test]
+    fn earth_solar_day_is_24_hours() {
+        let settings = settings::Settings {
+            rotational_period: 23.9344696,
+            orbital_period: 1.0000174,
+            ..test_settings()
+        };
+        assert!((settings.solar_day_length() - 86400.0).abs() < 1.0, "the solar day is {} s long", settings.solar_day_length());
+    }
+
+    #[test]
+    fn rover_without_sunlight_stops_when_the_battery_is_empty() {
+        let settings = settings::Settings {
+            rover_energy_model: true,
+            rover_solar_power: 0.0,
+            rover_battery_capacity: 1.0,
+            rover_energy_per_metre: 10.0,
+            simulation_time: 3600.0,
+            ..test_settings()
+        };
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        let mut distance = 0.0;
+        while !simulation.is_finished() {
+            simulation.step();
+            distance += simulation.marco_speeds[0] * settings.timestep;
         }
+        assert_eq!(simulation.marco_speeds[0], 0.0);
+        assert_eq!(simulation.battery_charge(0), 0.0);
+        // The whole battery was spent on walking, at 10 J per metre
+        assert!((distance - 360.0).abs() < 1e-6, "the rover walked {distance} m with 360 m worth of energy");
     }
-    if settings.generate_image {
-        if let Err(err) = sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::Plots)) {
-            println!("Error sending new stage (plots): {err}");
+
+    #[test]
+    fn star_starts_at_the_orbital_phase_and_local_solar_time() {
+        let tilt = 23.5_f64;
+        let start = |orbital_phase: f64, start_local_solar_time: f64| {
+            let simulation = Simulation::<f64>::new(settings::Settings {
+                start_lon: (-60.0_f64).to_radians(),
+                rotational_axis_tilt: tilt.to_radians(),
+                orbital_phase,
+                set_start_local_solar_time: true,
+                start_local_solar_time,
+                ..test_settings()
+            });
+            (simulation.point(0, data::Colour::new(0, 0, 0, 0)), simulation.local_solar_time(0))
+        };
+
+        let (solstice, local_solar_time) = start(0.0, 12.0);
+        assert!((solstice.subsolar_latitude - tilt).abs() < 1e-9);
+        assert!((solstice.subsolar_longitude + 60.0).abs() < 1e-9 && (local_solar_time - 12.0).abs() < 1e-9);
+        let (equinox, local_solar_time) = start(90.0, 6.0);
+        assert!(equinox.subsolar_latitude.abs() < 1e-9);
+        assert!((equinox.subsolar_longitude - 30.0).abs() < 1e-9 && (local_solar_time - 6.0).abs() < 1e-9);
+        let (winter, _) = start(180.0, 18.0);
+        assert!((winter.subsolar_latitude + tilt).abs() < 1e-9);
+        assert!((winter.subsolar_longitude + 150.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn marco_walks_at_most_the_maximum_hours_per_day() {
+        let settings = settings::Settings {
+            limit_walking_hours: true,
+            max_walking_hours: 2.0,
+            simulation_time: 12.0 * 3600.0,
+            ..test_settings()
+        };
+        let mut simulation = Simulation::<f64>::new(settings.clone());
+        let mut walking_time = 0.0;
+        while !simulation.is_finished() {
+            simulation.step();
+            if simulation.marco_speeds[0] > 0.0 {
+                walking_time += settings.timestep;
+            }
         }
-        if let Err(err) = generate_image(data, settings) {
-            println!("Failed to generate the plot: {err}");
+        assert!((walking_time - 2.0 * 3600.0).abs() < 1e-6, "Marco walked for {walking_time} s");
+    }
+
+    #[test]
+    fn every_start_of_the_grid_is_simulated_with_every_velocity() {
+        let settings = settings::Settings {
+            velocities_count: 2,
+            start_positions: settings::StartPositions::Grid,
+            start_grid: settings::StartGrid {
+                min_latitude: -30.0,
+                max_latitude: 30.0,
+                latitudes_count: 3,
+                min_longitude: -90.0,
+                max_longitude: 0.0,
+                longitudes_count: 2,
+            },
+            simulation_time: 600.0,
+            ..test_settings()
+        };
+        let data = simulate(settings, |_, _| Ok(()), |_, _| Ok(())).unwrap();
+        assert_eq!(data.len(), 12);
+        for (i, series) in data.iter().enumerate() {
+            let start = &series[0];
+            let (latitude, longitude) = ([-30.0, 0.0, 30.0][i / 4], [-90.0, 0.0][i / 2 % 2]);
+            assert_eq!(start.series, i);
+            assert!(
+                (start.latitude - latitude).abs() < 1e-9 && (start.longitude - longitude).abs() < 1e-9,
+                "series {i} starts at {}, {}",
+                start.latitude,
+                start.longitude
+            );
+            assert_eq!(start.velocity, data[i % 2][0].velocity);
         }
     }
-    if let Err(err) = sender.send(message_passers::Message::NewStage(message_passers::CalculationStage::End)) {
-        println!("Error sending new stage (end): {err}");
+
+    #[test]
+    fn marco_does_not_enter_impassable_terrain() {
+        // The whole northern hemisphere is impassable
+        let terrain = terrain::TerrainMask::new(std::path::PathBuf::from("north.png"), 4, 2, vec![true, true, true, true, false, false, false, false]).unwrap();
+        let distance_walked = |blocked_behaviour| {
+            let settings = settings::Settings {
+                start_lat: (-0.01_f64).to_radians(),
+                start_lon: (-90.0_f64).to_radians(),
+                simulation_time: 3600.0,
+                terrain_mask: Some(std::sync::Arc::new(terrain.clone())),
+                blocked_behaviour,
+                ..test_settings()
+            };
+            let data = simulate(settings, |_, _| Ok(()), |_, _| Ok(())).unwrap();
+            assert!(data[0].iter().all(|point| point.latitude <= 0.0), "Marco entered the impassable terrain");
+            let (first, last) = (&data[0][0], data[0].last().unwrap());
+            longitude_difference(last.longitude, first.longitude).hypot(last.latitude - first.latitude)
+        };
+        let stopped = distance_walked(terrain::BlockedBehaviour::Stop);
+        let slid = distance_walked(terrain::BlockedBehaviour::Slide);
+        assert!(stopped < 0.05, "Marco walked {stopped} deg instead of stopping at the boundary");
+        assert!(slid > 0.1, "Marco only slid {slid} deg along the boundary");
     }
-}
 
-fn generate_image(data: Vec<Vec<data::Data>>, settings: settings::Settings) -> Result<(), Box<dyn std::error::Error>> {
-    use plotters::prelude::*;
+    #[test]
+    fn round_off_shrinks_with_the_precision() {
+        fn final_position<T: precision::Float>() -> Vector3<f64> {
+            let simulation = continue_simulation(Simulation::<T>::new(test_settings()), Vec::new(), |_, _| Ok(()), |_, _| Ok(())).unwrap();
+            let last = simulation[0].last().unwrap();
+            let (latitude, longitude) = (last.latitude.to_radians(), last.longitude.to_radians());
+            Vector3::new(latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin())
+        }
+        let reference = final_position::<precision::DoubleDouble>();
+        let single_error = (final_position::<f32>() - reference).norm();
+        let double_error = (final_position::<f64>() - reference).norm();
+        assert!(double_error < 1e-9, "double precision is off by {double_error}");
+        assert!(single_error > double_error, "single precision ({single_error}) is not worse than double precision ({double_error})");
+    }
 
-    let scale_factor = settings.image_scale_factor;
-    let font = "sans";
+    #[test]
+    fn moon_in_front_of_the_star_eclipses_it() {
+        // A new moon in the ecliptic with Marco near the subsolar point at noon
+        let settings = |moon: bool, eclipse_behaviour: eclipse::EclipseBehaviour| settings::Settings {
+            start_lat: 22.5_f64.to_radians(),
+            rotational_axis_tilt: 23.5_f64.to_radians(),
+            set_start_local_solar_time: true,
+            start_local_solar_time: 12.0,
+            moon,
+            moon_inclination: 0.0,
+            moon_phase: 0.0,
+            eclipse_behaviour,
+            simulation_time: 6.0 * 3600.0,
+            ..test_settings()
+        };
 
-    let planet_radius = settings.planet_radius;
+        let mut simulation = Simulation::<f64>::new(settings(true, eclipse::EclipseBehaviour::Stop));
+        assert!(simulation.star_visible_fraction(0) < 0.1);
+        simulation.step();
+        assert_eq!(simulation.marco_speeds[0], 0.0);
+        while !simulation.is_finished() {
+            simulation.step();
+        }
+        let [eclipse] = simulation.eclipses[..] else {
+            panic!("{:?} is not a single eclipse", simulation.eclipses);
+        };
+        assert_eq!(eclipse.start, 0.0);
+        assert!(eclipse.max_coverage > 0.9, "{eclipse:?}");
+        // The moon moves on, so the eclipse ends and Marco walks again
+        assert!(eclipse.end < 3.0 * 3600.0, "{eclipse:?}");
+        assert_eq!(simulation.marco_speeds[0], 5.0);
 
-    let out_file_name = "plotters-doc-data/3d-plot.png";
+        // Marco stops while any part of the star is covered, so it is not daylight for him, unless he only slows down
+        assert!(!Simulation::<f64>::new(settings(true, eclipse::EclipseBehaviour::Stop)).is_in_daylight(0));
+        let mut slowed = Simulation::<f64>::new(settings(true, eclipse::EclipseBehaviour::Slow));
+        assert!(slowed.is_in_daylight(0));
+        slowed.step();
+        assert!(slowed.marco_speeds[0] > 0.0 && slowed.marco_speeds[0] < 0.5);
+
+        let mut without_moon = Simulation::<f64>::new(settings(false, eclipse::EclipseBehaviour::Stop));
+        without_moon.step();
+        assert_eq!(without_moon.marco_speeds[0], 5.0);
+        assert!(without_moon.eclipses.is_empty());
+    }
 
-    let area = BitMapBackend::new(out_file_name, ((1024.0 * scale_factor) as u32, (760.0 * scale_factor) as u32)).into_drawing_area();
+    #[test]
+    fn marco_does_not_walk_in_the_shadow_of_the_rings() {
+        // Noon at the northern summer solstice, so the rings shade the southern hemisphere
+        let settings = |start_latitude: f64, rings: bool| settings::Settings {
+            start_lat: start_latitude.to_radians(),
+            set_start_local_solar_time: true,
+            start_local_solar_time: 12.0,
+            rings,
+            ring_opacity: 0.75,
+            ..test_settings()
+        };
+        let first_speed = |settings: settings::Settings| {
+            let mut simulation = Simulation::<f64>::new(settings);
+            simulation.step();
+            simulation.marco_speeds[0]
+        };
 
-    area.fill(&WHITE)?;
+        let shaded = Simulation::<f64>::new(settings(-30.0, true));
+        assert!(shaded.is_in_ring_shadow(0));
+        assert!((shaded.ring_transmission(0) - 0.25).abs() < 1e-12);
+        assert_eq!(first_speed(settings(-30.0, true)), 0.0);
+        assert_eq!(first_speed(settings(-30.0, false)), 5.0);
+        assert_eq!(first_speed(settings(30.0, true)), 5.0);
 
-    let x_axis = (-(planet_radius * 1.1)..(planet_radius * 1.1)).step(planet_radius * 1.1 / 100.0);
-    let z_axis = (-(planet_radius * 1.1)..(planet_radius * 1.1)).step(planet_radius * 1.1 / 100.0);
+        let opaque = Simulation::<f64>::new(settings::Settings {
+            ring_opacity: 1.0,
+            ..settings(-30.0, true)
+        });
+        assert!(opaque.star_elevation_sine(0) > 0.0 && !opaque.is_in_daylight(0));
+        // Marco does not walk in the shadow of translucent rings either, so it is not daylight for him
+        assert!(!shaded.is_in_daylight(0));
+        assert!(Simulation::<f64>::new(settings(30.0, true)).is_in_daylight(0));
+    }
 
-    let mut chart =
-        ChartBuilder::on(&area)
-            .caption("Marco on a desert planet", (font, 20.0 * scale_factor))
-            .build_cartesian_3d(x_axis.clone(), -(planet_radius * 1.1)..(planet_radius * 1.1), z_axis.clone())?;
+    #[test]
+    fn single_start_position_is_not_rounded_through_degrees() {
+        // A latitude that does not survive the conversion to degrees and back
+        let start_lat = (1..1000).map(|i| i as f64 * 1e-3).find(|latitude| latitude.to_degrees().to_radians() != *latitude).unwrap();
+        let settings = settings::Settings {
+            start_lat,
+            start_lon: 0.0,
+            ..test_settings()
+        };
+        let simulation = Simulation::<f64>::new(settings.clone());
+        let expected = Vector3::new(start_lat.cos(), 0.0, start_lat.sin()) * settings.planet_radius;
+        assert_eq!(simulation.position(0), expected);
+    }
 
-    chart.with_projection(|mut pb| {
-        pb.yaw = 0.5 + std::f64::consts::PI;
-        pb.scale = 0.9;
-        pb.into_matrix()
-    });
+    #[test]
+    fn rest_schedule_follows_the_walking_window_and_the_zenith_rest() {
+        let mut simulation = Simulation::<f64>::new(settings::Settings {
+            walking_window: true,
+            zenith_rest_elevation: 70.0,
+            ..test_settings()
+        });
+        // The simulation starts at local noon
+        assert_eq!(simulation.local_solar_time(0), 12.0);
+        let mut may_walk_in = |start: f64, end: f64, zenith_rest: bool, elevation: f64| {
+            simulation.settings.walking_window_start = start;
+            simulation.settings.walking_window_end = end;
+            simulation.settings.zenith_rest = zenith_rest;
+            simulation.may_walk(0, elevation.to_radians().sin())
+        };
+        assert!(may_walk_in(8.0, 18.0, false, 0.0));
+        assert!(!may_walk_in(13.0, 18.0, false, 0.0));
+        assert!(!may_walk_in(8.0, 12.0, false, 0.0), "the end of the window is excluded");
+        // A window starting later than it ends goes over midnight
+        assert!(!may_walk_in(22.0, 6.0, false, 0.0));
+        assert!(may_walk_in(11.0, 10.0, false, 0.0));
+        assert!(!may_walk_in(13.0, 11.0, false, 0.0));
+        // Marco rests while the star is higher than the zenith rest elevation
+        assert!(!may_walk_in(8.0, 18.0, true, 80.0));
+        assert!(may_walk_in(8.0, 18.0, true, 60.0));
+        assert!(may_walk_in(8.0, 18.0, false, 80.0));
 
-    chart
-        .configure_axes()
-        .light_grid_style(ShapeStyle::from(BLACK.mix(0.15)).stroke_width(scale_factor as u32))
-        .max_light_lines(3)
-        .label_style((font, 10.0 * scale_factor))
-        .draw()?;
-
-    {
-        // Draw the planet
-        let resolution_lines_of_longitude = 10_000; // planet_radius as i32;
-        let lines_of_longitude_count = 2000;
-        let t = (0..=resolution_lines_of_longitude).map(|t| t as f64 / (resolution_lines_of_longitude as f64) * std::f64::consts::TAU);
-        let y = t.clone().map(|t| planet_radius * t.sin());
-        let z = t.map(|t| planet_radius * t.cos());
-        for i in 0..lines_of_longitude_count {
-            let lon = i as f64 / (lines_of_longitude_count as f64) * std::f64::consts::PI;
-            let x = z.clone().map(|z| z * lon.sin());
-            let z = z.clone().map(|z| z * lon.cos());
-            let iter = x.zip(y.clone()).zip(z).map(|((x, y), z)| (x, y, z));
-            chart.draw_series(LineSeries::new(iter, ShapeStyle::from(BLACK.mix(0.3)).stroke_width((scale_factor / 4.0).max(1.0) as u32)))?;
-        }
-    }
-
-    // Draw the paths
-    for i in (0..data.len()).rev() {
-        if data[i].is_empty() {
-            continue;
-        }
-        chart.draw_series(LineSeries::new(
-            (0..data[i].len()).map(|t| {
-                let point = &data[i][t];
-                let lat = point.latitude.to_radians();
-                let lon = point.longitude.to_radians();
-                let (x, y, z) = (planet_radius * lat.cos() * lon.cos(), planet_radius * lat.cos() * lon.sin(), planet_radius * lat.sin());
-                (y, z, x)
-            }),
-            ShapeStyle::from(RGBAColor(data[i][0].colour.r(), data[i][0].colour.g(), data[i][0].colour.b(), data[i][0].colour.a() as f64 / 255.0)),
-        ))?;
-    }
-
-    /*chart
-        .draw_series(
-            SurfaceSeries::xoz(
-                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
-                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
-                |x, z| {
-                    let y_2 = radius * radius - (x * x + z * z);
-                    if y_2 < 0.0 {
-                        0.0
-                    } else {
-                        y_2.sqrt()
-                    }
-                },
-            )
-            .style(BLUE.mix(0.2).filled()),
-        )?
-        .label("Surface")
-        .legend(|(x, y)| Rectangle::new([(x + 5, y - 5), (x + 15, y + 5)], BLUE.mix(0.5).filled()));
-
-    chart
-        .draw_series(
-            SurfaceSeries::xoz(
-                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
-                (-100..100).map(|f| f as f64 / 100.0 * (radius * 1.1)),
-                |x, z| {
-                    let y_2 = radius * radius - (x * x + z * z);
-                    if y_2 < 0.0 {
-                        0.0
-                    } else {
-                        -y_2.sqrt()
-                    }
-                },
-            )
-            .style(BLUE.mix(0.2).filled()),
-        )?
-        .label("Surface")
-        .legend(|(x, y)| Rectangle::new([(x + 5, y - 5), (x + 15, y + 5)], BLUE.mix(0.5).filled()));*/
-
-    /*chart
-    .draw_series(LineSeries::new(
-        (-100..100)
-            .map(|y| y as f64 / 100.0 * (radius * 1.1))
-            .map(|y| ((radius * 1.1) * (y * 10.0).sin(), y, (radius * 1.1) * (y * 10.0).cos())),
-        &BLACK,
-    ))?
-    .label("Line")
-    .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK));*/
-
-    chart.configure_series_labels().border_style(BLACK).draw()?;
-
-    // To avoid the IO failure being ignored silently, we manually call the present function
-    area.present()
-        .expect("Unable to write result to file, please make sure 'plotters-doc-data' dir exists under current dir");
-    println!("Result has been saved to {}", out_file_name);
-    Ok(())
+        let settings = settings::Settings {
+            walking_window: true,
+            walking_window_start: 8.0,
+            walking_window_end: 8.0,
+            ..test_settings()
+        };
+        assert!(settings.validate().is_err(), "an empty walking window");
+    }
 }
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::str::FromStr;
+
+use nalgebra::Vector3;
+
+use crate::error;
+
+/// A date and time in UTC of the proleptic Gregorian calendar.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UtcDateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: f64,
+}
+
+impl Default for UtcDateTime {
+    /// The June solstice of 2024.
+    fn default() -> Self {
+        Self {
+            year: 2024,
+            month: 6,
+            day: 20,
+            hour: 20,
+            minute: 51,
+            second: 0.0,
+        }
+    }
+}
+
+impl UtcDateTime {
+    fn days_in_month(year: i32, month: u32) -> u32 {
+        match month {
+            2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), error::Error> {
+        let valid = (1..=12).contains(&self.month) && (1..=Self::days_in_month(self.year, self.month)).contains(&self.day) && self.hour < 24 && self.minute < 60 && (0.0..60.0).contains(&self.second);
+        if valid {
+            Ok(())
+        } else {
+            Err(error::Error::InvalidSettings(format!("{self} is not a valid date and time")))
+        }
+    }
+
+    /// The Julian day, using the algorithm of Meeus, "Astronomical Algorithms" (1998), chapter 7.
+    pub fn julian_day(&self) -> f64 {
+        let (year, month) = if self.month <= 2 { (self.year - 1, self.month + 12) } else { (self.year, self.month) };
+        let century = (year as f64 / 100.0).floor();
+        let gregorian_correction = 2.0 - century + (century / 4.0).floor();
+        let day = self.day as f64 + (self.hour as f64 + (self.minute as f64 + self.second / 60.0) / 60.0) / 24.0;
+        (365.25 * (year as f64 + 4716.0)).floor() + (30.6001 * (month as f64 + 1.0)).floor() + day + gregorian_correction - 1524.5
+    }
+}
+
+impl fmt::Display for UtcDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
+
+impl FromStr for UtcDateTime {
+    type Err = error::Error;
+
+    /// Parses dates such as `2024-06-20 20:51:00`, the seconds are optional and the ISO 8601 `T` separator and `Z` suffix are accepted.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || error::Error::InvalidSettings(format!("\"{text}\" is not a date and time in the format YYYY-MM-DD HH:MM:SS"));
+        let trimmed = text.trim();
+        let trimmed = trimmed.strip_suffix('Z').unwrap_or(trimmed);
+        let (date, time) = trimmed.split_once(['T', ' ']).ok_or_else(invalid)?;
+        // The year can be negative, so it is split from the right
+        let mut date_parts = date.rsplitn(3, '-');
+        let (day, month, year) = (date_parts.next(), date_parts.next(), date_parts.next());
+        let mut time_parts = time.split(':');
+        let (hour, minute, second) = (time_parts.next(), time_parts.next(), time_parts.next().unwrap_or("0"));
+        if time_parts.next().is_some() {
+            return Err(invalid());
+        }
+        let date_time = Self {
+            year: year.and_then(|year| year.parse().ok()).ok_or_else(invalid)?,
+            month: month.and_then(|month| month.parse().ok()).ok_or_else(invalid)?,
+            day: day.and_then(|day| day.parse().ok()).ok_or_else(invalid)?,
+            hour: hour.and_then(|hour| hour.parse().ok()).ok_or_else(invalid)?,
+            minute: minute.and_then(|minute| minute.parse().ok()).ok_or_else(invalid)?,
+            second: second.parse().map_err(|_| invalid())?,
+        };
+        date_time.validate()?;
+        Ok(date_time)
+    }
+}
+
+/// The position of the Sun as seen from the Earth, computed by the low precision algorithm of the Astronomical Almanac, which is accurate to about 0.01 deg between 1950 and 2050.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolarPosition {
+    /// The latitude and longitude (deg) of the point the Sun is in the zenith of.
+    pub subsolar_latitude: f64,
+    pub subsolar_longitude: f64,
+    /// The obliquity of the ecliptic (deg), the tilt of the rotational axis of the Earth.
+    pub obliquity: f64,
+    /// The longitude (deg) the north ecliptic pole is above, the pole is at the latitude of 90 deg minus the obliquity.
+    pub ecliptic_pole_longitude: f64,
+}
+
+impl SolarPosition {
+    pub fn at(date_time: &UtcDateTime) -> Self {
+        let days = date_time.julian_day() - 2451545.0;
+        let mean_longitude = 280.460 + 0.9856474 * days;
+        let mean_anomaly = (357.528 + 0.9856003 * days).to_radians();
+        let ecliptic_longitude = (mean_longitude + 1.915 * mean_anomaly.sin() + 0.020 * (2.0 * mean_anomaly).sin()).to_radians();
+        let obliquity = 23.439 - 0.0000004 * days;
+
+        let right_ascension = (obliquity.to_radians().cos() * ecliptic_longitude.sin()).atan2(ecliptic_longitude.cos()).to_degrees();
+        let declination = (obliquity.to_radians().sin() * ecliptic_longitude.sin()).asin().to_degrees();
+        // The Greenwich mean sidereal time (deg), the right ascension of the meridian of Greenwich
+        let sidereal_time = (18.697374558 + 24.06570982441908 * days) * 15.0;
+        let longitude = |right_ascension: f64| (right_ascension - sidereal_time + 180.0).rem_euclid(360.0) - 180.0;
+
+        Self {
+            subsolar_latitude: declination,
+            subsolar_longitude: longitude(right_ascension),
+            obliquity,
+            // The north ecliptic pole has the right ascension of 18 h
+            ecliptic_pole_longitude: longitude(270.0),
+        }
+    }
+
+    /// The direction towards the Sun and the axis of the orbit of the Earth in the coordinates of the simulation, the z axis being the rotational axis and the x axis the prime meridian.
+    pub fn directions(&self) -> (Vector3<f64>, Vector3<f64>) {
+        let direction = |latitude: f64, longitude: f64| {
+            let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+            Vector3::new(latitude.cos() * longitude.cos(), latitude.cos() * longitude.sin(), latitude.sin())
+        };
+        (
+            direction(self.subsolar_latitude, self.subsolar_longitude),
+            direction(90.0 - self.obliquity, self.ecliptic_pole_longitude),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solar_position_matches_the_almanac() {
+        let date = |text: &str| text.parse::<UtcDateTime>().unwrap();
+        assert_eq!(date("2000-01-01T12:00:00Z").julian_day(), 2451545.0);
+
+        // The Sun is above the tropic of Cancer at the June solstice
+        let solstice = SolarPosition::at(&date("2024-06-20 20:51"));
+        assert!((solstice.subsolar_latitude - 23.44).abs() < 0.01, "{solstice:?}");
+        // The equation of time is about -14 min in mid February, so at noon UTC the Sun has not reached the prime meridian yet
+        let february = SolarPosition::at(&date("2024-02-11 12:00:00"));
+        assert!((february.subsolar_latitude + 14.1).abs() < 0.1, "{february:?}");
+        assert!((february.subsolar_longitude - 3.55).abs() < 0.05, "{february:?}");
+
+        // The Sun is always on the ecliptic
+        let (sun, ecliptic_axis) = february.directions();
+        assert!(sun.dot(&ecliptic_axis).abs() < 1e-9);
+
+        assert!("2023-02-29 12:00:00".parse::<UtcDateTime>().is_err());
+        assert_eq!(date("2024-02-29 06:30:15.5").to_string(), "2024-02-29 06:30:15.5");
+    }
+}
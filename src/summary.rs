@@ -0,0 +1,59 @@
+use crate::data;
+
+/// A way to summarise the run of a single velocity into one or more values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Summary {
+    #[default]
+    FinalLatitude,
+    /// The mean latitude over the end of the run.
+    MeanLatitude,
+    /// The average rate of change of the longitude over the end of the run, in degrees per day.
+    LongitudeDriftRate,
+    /// All the latitudes over the end of the run, which shows oscillating behaviour as in a bifurcation diagram.
+    LateLatitudes,
+}
+
+impl Summary {
+    pub const ALL: [Self; 4] = [Self::FinalLatitude, Self::MeanLatitude, Self::LongitudeDriftRate, Self::LateLatitudes];
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::FinalLatitude | Self::MeanLatitude | Self::LateLatitudes => "deg",
+            Self::LongitudeDriftRate => "deg/d",
+        }
+    }
+
+    /// Summarises the points of a single velocity, `window` being the length of the end of the run (s) some of the summaries are calculated over.
+    /// Returns no values if there are not enough points.
+    pub fn summarise(&self, points: &[data::Data], window: f64) -> Vec<f64> {
+        let Some(last) = points.last() else {
+            return Vec::new();
+        };
+        let start = points.partition_point(|point| point.time < last.time - window);
+        let late = &points[start..];
+        match self {
+            Self::FinalLatitude => vec![last.latitude],
+            Self::MeanLatitude => vec![late.iter().map(|point| point.latitude).sum::<f64>() / late.len() as f64],
+            Self::LongitudeDriftRate => {
+                if late.len() < 2 || late[late.len() - 1].time <= late[0].time {
+                    return Vec::new();
+                }
+                // Unwrap the longitude so crossing the antimeridian does not count as a jump of 360 degrees
+                let longitude_change = late.windows(2).map(|pair| (pair[1].longitude - pair[0].longitude + 180.0).rem_euclid(360.0) - 180.0).sum::<f64>();
+                vec![longitude_change / ((late[late.len() - 1].time - late[0].time) / 86400.0)]
+            }
+            Self::LateLatitudes => late.iter().map(|point| point.latitude).collect(),
+        }
+    }
+}
+
+impl AsRef<str> for Summary {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::FinalLatitude => "Final latitude",
+            Self::MeanLatitude => "Mean latitude",
+            Self::LongitudeDriftRate => "Longitude drift rate",
+            Self::LateLatitudes => "All late latitudes",
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use nalgebra::Vector3;
+
+use crate::error;
+
+/// What Marco does when his path leads into impassable terrain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockedBehaviour {
+    /// Marco walks along the boundary in the direction closest to the star.
+    #[default]
+    Slide,
+    /// Marco waits until the star moves so that his path is free again.
+    Stop,
+}
+
+impl BlockedBehaviour {
+    pub const ALL: [Self; 2] = [Self::Slide, Self::Stop];
+}
+
+impl AsRef<str> for BlockedBehaviour {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::Slide => "Slide along the boundary",
+            Self::Stop => "Stop",
+        }
+    }
+}
+
+/// A map of impassable terrain in an equirectangular projection, the first row being the north pole and the first column -180 deg longitude.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TerrainMask {
+    pub path: PathBuf,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+}
+
+impl fmt::Debug for TerrainMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TerrainMask({}, {}x{})", self.path.display(), self.width, self.height)
+    }
+}
+
+impl TerrainMask {
+    /// Creates a mask from its pixels, row by row, `true` being impassable.
+    pub fn new(path: PathBuf, width: usize, height: usize, blocked: Vec<bool>) -> Result<Self, error::Error> {
+        if width == 0 || height == 0 || blocked.len() != width * height {
+            return Err(error::Error::Terrain(format!("a {width}x{height} mask can not have {} pixels", blocked.len())));
+        }
+        Ok(Self { path, width, height, blocked })
+    }
+
+    /// Loads a mask from a greyscale or colour PNG image, dark pixels are impassable and transparent ones passable.
+    #[cfg(feature = "terrain")]
+    pub fn load(path: &Path) -> Result<Self, error::Error> {
+        let mut decoder = png::Decoder::new(std::io::BufReader::new(std::fs::File::open(path)?));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let channels = info.color_type.samples();
+        let blocked = buffer[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|pixel| {
+                let (brightness, alpha) = match *pixel {
+                    [grey] => (grey as f64, 255),
+                    [grey, alpha] => (grey as f64, alpha),
+                    [r, g, b] => (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64, 255),
+                    [r, g, b, alpha] => (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64, alpha),
+                    _ => (255.0, 0),
+                };
+                alpha >= 128 && brightness < 128.0
+            })
+            .collect();
+        Self::new(path.to_path_buf(), info.width as usize, info.height as usize, blocked)
+    }
+
+    #[cfg(not(feature = "terrain"))]
+    pub fn load(_path: &Path) -> Result<Self, error::Error> {
+        Err(error::Error::Terrain(String::from("the program was compiled without the `terrain` feature")))
+    }
+
+    /// Whether the terrain at the given latitude and longitude (deg) is impassable.
+    pub fn is_blocked(&self, latitude: f64, longitude: f64) -> bool {
+        let column = (((longitude + 180.0) / 360.0 * self.width as f64).floor() as i64).rem_euclid(self.width as i64) as usize;
+        let row = (((90.0 - latitude) / 180.0 * self.height as f64).floor().max(0.0) as usize).min(self.height - 1);
+        self.blocked[row * self.width + column]
+    }
+
+    /// Whether the terrain at the given position on the planet is impassable.
+    pub fn is_blocked_at(&self, position: &Vector3<f64>) -> bool {
+        let position = position.normalize();
+        self.is_blocked(position.z.clamp(-1.0, 1.0).asin().to_degrees(), position.y.atan2(position.x).to_degrees())
+    }
+
+    /// Samples the mask on a grid of `rows` by `columns` cells and returns the impassable areas as (min latitude, max latitude, min longitude, max longitude) rectangles (deg).
+    /// Neighbouring impassable cells of a row are merged into a single rectangle.
+    pub fn blocked_rectangles(&self, rows: usize, columns: usize) -> Vec<(f64, f64, f64, f64)> {
+        let (cell_height, cell_width) = (180.0 / rows as f64, 360.0 / columns as f64);
+        let mut rectangles = Vec::new();
+        for row in 0..rows {
+            let max_latitude = 90.0 - row as f64 * cell_height;
+            let min_latitude = max_latitude - cell_height;
+            let mut run_start = None;
+            for column in 0..=columns {
+                let blocked = column < columns && self.is_blocked(max_latitude - cell_height / 2.0, -180.0 + (column as f64 + 0.5) * cell_width);
+                match (blocked, run_start) {
+                    (true, None) => run_start = Some(column),
+                    (false, Some(start)) => {
+                        rectangles.push((min_latitude, max_latitude, -180.0 + start as f64 * cell_width, -180.0 + column as f64 * cell_width));
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        rectangles
+    }
+}
@@ -0,0 +1,187 @@
+use crate::settings;
+
+/// A unit of time to show the simulated time in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeUnit {
+    #[default]
+    Seconds,
+    Hours,
+    Days,
+    Years,
+    /// The time between two consecutive noons on the simulated planet.
+    LocalSolarDays,
+}
+
+impl TimeUnit {
+    pub const ALL: [Self; 5] = [Self::Seconds, Self::Hours, Self::Days, Self::Years, Self::LocalSolarDays];
+
+    /// The length of the unit in seconds.
+    pub fn seconds(&self, settings: &settings::Settings) -> f64 {
+        match self {
+            Self::Seconds => 1.0,
+            Self::Hours => 3600.0,
+            Self::Days => 86400.0,
+            Self::Years => 365.25 * 86400.0,
+            Self::LocalSolarDays => settings.solar_day_length(),
+        }
+    }
+
+    /// The spacings (in this unit) of the ticks of a time axis from the finest to the coarsest, falling on whole minutes, hours, days or years.
+    fn tick_spacings(&self) -> &'static [f64] {
+        const HOUR: f64 = 3600.0;
+        const DAY: f64 = 86400.0;
+        const YEAR: f64 = 365.25 * DAY;
+        match self {
+            Self::Seconds => &[
+                1.0,
+                2.0,
+                5.0,
+                10.0,
+                15.0,
+                30.0,
+                60.0,
+                120.0,
+                300.0,
+                600.0,
+                900.0,
+                1800.0,
+                HOUR,
+                2.0 * HOUR,
+                3.0 * HOUR,
+                6.0 * HOUR,
+                12.0 * HOUR,
+                DAY,
+                2.0 * DAY,
+                5.0 * DAY,
+                10.0 * DAY,
+                30.0 * DAY,
+                YEAR,
+            ],
+            Self::Hours => &[1.0 / 60.0, 5.0 / 60.0, 0.25, 0.5, 1.0, 2.0, 3.0, 6.0, 12.0, 24.0, 48.0, 120.0, 240.0, 720.0, YEAR / HOUR],
+            Self::Days | Self::LocalSolarDays => &[1.0 / 24.0, 3.0 / 24.0, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 100.0, 365.25],
+            Self::Years => &[DAY / YEAR, 7.0 * DAY / YEAR, 1.0 / 12.0, 0.25, 0.5, 1.0],
+        }
+    }
+
+    /// The ticks of a time axis in this unit from `min` to `max`, at least `min_spacing` apart, as (time, spacing) pairs.
+    /// The spacing of a tick is the coarsest of the three finest spacings it falls on, so ticks on whole days can be drawn bolder than those on hours.
+    pub fn ticks(&self, min: f64, max: f64, min_spacing: f64) -> Vec<(f64, f64)> {
+        let spacings = self.tick_spacings();
+        let coarsest = spacings[spacings.len() - 1];
+        // Beyond the listed spacings, the coarsest one is multiplied by 2, 5, 10, 20, 50 and so on
+        let spacing = |i: usize| match i.checked_sub(spacings.len()) {
+            None => spacings[i],
+            Some(beyond) => coarsest * [2.0, 5.0, 10.0][beyond % 3] * 10.0_f64.powi((beyond / 3) as i32),
+        };
+        if !(min.is_finite() && max.is_finite() && min_spacing.is_finite() && min_spacing > 0.0) {
+            return Vec::new();
+        }
+        let Some(finest) = (0..1000).find(|&i| spacing(i) >= min_spacing) else {
+            return Vec::new();
+        };
+        let levels = [spacing(finest), spacing(finest + 1), spacing(finest + 2)];
+        let (first, last) = ((min / levels[0]).ceil() as i64, (max / levels[0]).floor() as i64);
+        // A range too wide for the spacing would never be drawn anyway
+        if last - first > 10000 {
+            return Vec::new();
+        }
+        (first..=last)
+            .map(|i| {
+                let time = i as f64 * levels[0];
+                let is_multiple = |level: &f64| {
+                    let ratio = time / level;
+                    (ratio - ratio.round()).abs() < 1e-6
+                };
+                (time, levels.iter().rev().copied().find(is_multiple).unwrap_or(levels[0]))
+            })
+            .collect()
+    }
+
+    /// Formats the time of a tick of an axis in this unit, ticks between whole hours or days being shown as the time of day.
+    pub fn format_tick(&self, time: f64) -> String {
+        let is_whole = (time - time.round()).abs() < 1e-6;
+        match self {
+            _ if is_whole => format!("{} {}", time.round(), self.symbol()),
+            Self::Hours => format_calendar(time * 3600.0),
+            Self::Days => format_calendar(time * 86400.0),
+            _ => format!("{time:.3} {}", self.symbol()),
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Seconds => "s",
+            Self::Hours => "h",
+            Self::Days => "d",
+            Self::Years => "yr",
+            Self::LocalSolarDays => "solar days",
+        }
+    }
+}
+
+impl AsRef<str> for TimeUnit {
+    fn as_ref(&self) -> &str {
+        match *self {
+            Self::Seconds => "Seconds",
+            Self::Hours => "Hours",
+            Self::Days => "Days",
+            Self::Years => "Years",
+            Self::LocalSolarDays => "Local solar days",
+        }
+    }
+}
+
+/// Formats a time in seconds as years, days, hours, minutes and seconds, for example `1 yr 12 d 03:45:10`.
+pub fn format_calendar(seconds: f64) -> String {
+    // Rounding the parts instead of the total would show almost a whole day as 24:00:00
+    let total = seconds.abs().round();
+    let sign = if seconds < 0.0 && total > 0.0 { "-" } else { "" };
+    let year = 365.25 * 86400.0;
+    let years = (total / year).floor();
+    let rest = total - years * year;
+    let days = (rest / 86400.0).floor();
+    let rest = (rest - days * 86400.0) as u64;
+    let (hours, minutes, seconds) = (rest / 3600, rest / 60 % 60, rest % 60);
+    if years > 0.0 {
+        format!("{sign}{years} yr {days} d {hours:02}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{sign}{days} d {hours:02}:{minutes:02}:{seconds:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_fall_on_whole_hours_and_days() {
+        // About 0.15 d apart at least, so every 6 h, with whole days and every other day bolder
+        let ticks = TimeUnit::Days.ticks(-0.1, 2.1, 0.15);
+        assert_eq!(ticks.iter().map(|(time, _)| time * 24.0).collect::<Vec<f64>>(), (0..=8).map(|i| i as f64 * 6.0).collect::<Vec<f64>>());
+        assert_eq!(ticks[1].1, 0.25);
+        assert_eq!(ticks[2].1, 0.5);
+        assert_eq!(ticks[4].1, 1.0);
+
+        let hours = TimeUnit::Seconds.ticks(0.0, 86400.0, 3000.0);
+        assert_eq!(hours.len(), 25);
+        assert!(hours.iter().all(|(time, _)| time % 3600.0 == 0.0));
+
+        // Far beyond the listed spacings the ticks are on whole multiples of years
+        let centuries = TimeUnit::Years.ticks(0.0, 1000.0, 80.0);
+        assert_eq!(centuries.iter().map(|(time, _)| *time).collect::<Vec<f64>>(), (0..=10).map(|i| i as f64 * 100.0).collect::<Vec<f64>>());
+        assert!(TimeUnit::Hours.ticks(0.0, 1.0, f64::NAN).is_empty());
+
+        assert_eq!(TimeUnit::Days.format_tick(2.0), "2 d");
+        assert_eq!(TimeUnit::Days.format_tick(1.25), "1 d 06:00:00");
+        assert_eq!(TimeUnit::Years.format_tick(0.25), "0.250 yr");
+    }
+
+    #[test]
+    fn calendar_rounds_to_whole_seconds_before_splitting() {
+        assert_eq!(format_calendar(86399.6), "1 d 00:00:00");
+        assert_eq!(format_calendar(86399.4), "0 d 23:59:59");
+        assert_eq!(format_calendar(365.25 * 86400.0 + 3600.0 * 3.0 + 45.0 * 60.0 + 10.0), "1 yr 0 d 03:45:10");
+        assert_eq!(format_calendar(-0.4), "0 d 00:00:00");
+        assert_eq!(format_calendar(-90.0), "-0 d 00:01:30");
+    }
+}